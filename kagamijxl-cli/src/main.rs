@@ -0,0 +1,226 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Parser, Subcommand};
+use image::{codecs::jpeg::JpegDecoder, ColorType, DynamicImage, ImageDecoder, ImageEncoder};
+use kagamijxl::{reconstruct_jpeg, Decoder, Encoder, JpegFrame, JxlImageDecoder, JxlImageEncoder};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[clap(version, about = "Encodes, decodes and inspects JPEG XL images")]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /** Encodes an image in PNG, JPEG or PNM to JPEG XL */
+    Encode(EncodeArgs),
+    /** Decodes a JPEG XL image to PNG or PNM, or to the original JPEG file if possible */
+    Decode(DecodeArgs),
+    /** Prints the header information of a JPEG XL image */
+    Info(InfoArgs),
+    /** Losslessly transcodes a JPEG file to JPEG XL */
+    TranscodeJpeg(TranscodeJpegArgs),
+}
+
+#[derive(Args)]
+struct EncodeArgs {
+    input: PathBuf,
+    output: PathBuf,
+    /** Butteraugli distance, where 0 is lossless and 1 is visually lossless */
+    #[clap(short, long, conflicts_with_all = &["quality", "lossless"])]
+    distance: Option<f32>,
+    /** Quality from 0 to 100, where 100 is lossless */
+    #[clap(short, long, conflicts_with = "lossless")]
+    quality: Option<f32>,
+    /** Encoder effort from 1 (fastest) to 9 (slowest) */
+    #[clap(short, long)]
+    effort: Option<i32>,
+    #[clap(long)]
+    lossless: bool,
+}
+
+#[derive(Args)]
+struct DecodeArgs {
+    input: PathBuf,
+    /** The format is chosen by the extension; .jpg or .jpeg reconstructs the original JPEG file */
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct InfoArgs {
+    input: PathBuf,
+}
+
+#[derive(Args)]
+struct TranscodeJpegArgs {
+    input: PathBuf,
+    output: PathBuf,
+    /** Encoder effort from 1 (fastest) to 9 (slowest) */
+    #[clap(short, long)]
+    effort: Option<i32>,
+    /** Skips reconstructing the JPEG file from the output to compare it with the input */
+    #[clap(long)]
+    no_verify: bool,
+}
+
+/** kagamijxl errors don't implement `Error`, so they are reported by their debug output */
+fn error(err: impl std::fmt::Debug) -> Box<dyn Error> {
+    format!("{:?}", err).into()
+}
+
+fn encode(args: &EncodeArgs) -> Result<()> {
+    let image = image::open(&args.input)?;
+
+    let encoder = Encoder {
+        lossless: if args.lossless { Some(true) } else { None },
+        effort: args.effort,
+        distance: args.distance,
+        quality: args.quality,
+        ..Default::default()
+    };
+
+    let writer = BufWriter::new(File::create(&args.output)?);
+    JxlImageEncoder::with_encoder(writer, encoder).write_image(
+        image.as_bytes(),
+        image.width(),
+        image.height(),
+        image.color(),
+    )?;
+    Ok(())
+}
+
+fn is_jpeg_path(path: &Path) -> bool {
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    matches!(
+        extension.map(str::to_ascii_lowercase).as_deref(),
+        Some("jpg" | "jpeg")
+    )
+}
+
+fn decode(args: &DecodeArgs) -> Result<()> {
+    let data = std::fs::read(&args.input)?;
+
+    if is_jpeg_path(&args.output) {
+        if let Some(jpeg) = reconstruct_jpeg(&data).map_err(error)? {
+            std::fs::write(&args.output, jpeg)?;
+            return Ok(());
+        }
+    }
+
+    let image = DynamicImage::from_decoder(JxlImageDecoder::new(&data[..])?)?;
+    // PNG and PNM have no float samples, so keep as much precision as they can hold
+    let image = match image {
+        DynamicImage::ImageRgb32F(_) => DynamicImage::ImageRgb16(image.into_rgb16()),
+        DynamicImage::ImageRgba32F(_) => DynamicImage::ImageRgba16(image.into_rgba16()),
+        image => image,
+    };
+    image.save(&args.output)?;
+    Ok(())
+}
+
+fn info(args: &InfoArgs) -> Result<()> {
+    let data = std::fs::read(&args.input)?;
+
+    let decoder = Decoder {
+        no_full_image: true,
+        ..Default::default()
+    };
+    let result = decoder.decode(&data).map_err(error)?;
+    let basic_info = &result.basic_info;
+
+    println!("Size: {}x{}", basic_info.xsize, basic_info.ysize);
+    println!(
+        "Color channels: {}{}",
+        basic_info.num_color_channels,
+        if basic_info.alpha_bits > 0 {
+            " with alpha"
+        } else {
+            ""
+        }
+    );
+    println!(
+        "Bits per sample: {}{}",
+        basic_info.bits_per_sample,
+        if basic_info.exponent_bits_per_sample > 0 {
+            " (float)"
+        } else {
+            ""
+        }
+    );
+    println!("Orientation: {}", basic_info.orientation);
+    println!("Container: {}", basic_info.have_container != 0);
+
+    if basic_info.have_animation != 0 {
+        let animation = &basic_info.animation;
+        println!(
+            "Animation: {} frames, {} loops, {}/{} ticks per second",
+            result.frames.len(),
+            animation.num_loops,
+            animation.tps_numerator,
+            animation.tps_denominator
+        );
+    }
+    for (index, frame) in result.frames.iter().enumerate() {
+        let name = match frame.name.as_str() {
+            "" => String::new(),
+            name => format!(" \"{}\"", name),
+        };
+        println!("Frame {}{}: duration {}", index, name, frame.duration);
+    }
+
+    let jpeg = reconstruct_jpeg(&data).map_err(error)?;
+    println!("JPEG reconstruction: {}", jpeg.is_some());
+    Ok(())
+}
+
+fn transcode_jpeg(args: &TranscodeJpegArgs) -> Result<()> {
+    let jpeg = std::fs::read(&args.input)?;
+    let jpeg_decoder = JpegDecoder::new(BufReader::new(&jpeg[..]))?;
+    let (xsize, ysize) = jpeg_decoder.dimensions();
+
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = xsize;
+    encoder.basic_info.ysize = ysize;
+    encoder.basic_info.alpha_bits = 0;
+    encoder.basic_info.num_extra_channels = 0;
+    if jpeg_decoder.color_type() == ColorType::L8 {
+        encoder.basic_info.num_color_channels = 1;
+    }
+    encoder.jpeg.effort = args.effort;
+    encoder.jpeg.verify = !args.no_verify;
+
+    let encoded = encoder
+        .encode_frame(&JpegFrame { data: &jpeg })
+        .map_err(error)?;
+    std::fs::write(&args.output, encoded)?;
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    match &cli.command {
+        Command::Encode(args) => encode(args),
+        Command::Decode(args) => decode(args),
+        Command::Info(args) => info(args),
+        Command::TranscodeJpeg(args) => transcode_jpeg(args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cli;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,120 @@
+use image::{ColorType, ImageEncoder};
+use kagamijxl::JxlImageEncoder;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+fn sample_path(name: &str) -> PathBuf {
+    PathBuf::from(MANIFEST_DIR)
+        .join("../kagamijxl/tests/resources")
+        .join(name)
+}
+
+/** A path in the temporary directory that no other test or test run uses */
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("kagamijxl-cli-{}-{}", std::process::id(), name))
+}
+
+/** Runs the CLI and returns its standard output, failing when it doesn't succeed */
+fn run(args: &[&Path]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_kagamijxl"))
+        .args(args)
+        .output()
+        .expect("Failed to run the CLI");
+    assert!(
+        output.status.success(),
+        "The CLI failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("The output should be UTF-8")
+}
+
+#[test]
+fn test_cli_info() {
+    let output = run(&[Path::new("info"), &sample_path("sample.jxl")]);
+    assert!(output.contains("Size: 1404x936"));
+    assert!(output.contains("JPEG reconstruction: false"));
+
+    let output = run(&[Path::new("info"), &sample_path("spinfox.jxl")]);
+    assert!(output.contains("Animation: 25 frames"));
+}
+
+#[test]
+fn test_cli_decode() {
+    let output = temp_path("decode.png");
+    run(&[Path::new("decode"), &sample_path("sample.jxl"), &output]);
+
+    let image = image::open(&output).expect("Failed to read the output");
+    std::fs::remove_file(&output).unwrap();
+    assert_eq!((image.width(), image.height()), (1404, 936));
+}
+
+#[test]
+fn test_cli_decode_float() {
+    let input = temp_path("float.jxl");
+    let samples = [0.25f32, 0.5, 1.0, 1.0];
+    let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_ne_bytes()).collect();
+    let mut encoded = Vec::new();
+    JxlImageEncoder::new(&mut encoded)
+        .write_image(&bytes, 1, 1, ColorType::Rgba32F)
+        .expect("Failed to encode");
+    std::fs::write(&input, encoded).unwrap();
+
+    let output = temp_path("float.png");
+    run(&[Path::new("decode"), &input, &output]);
+
+    let image = image::open(&output).expect("Failed to read the output");
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert_eq!(image.color(), ColorType::Rgba16);
+}
+
+#[test]
+fn test_cli_encode() {
+    let input = temp_path("encode.png");
+    #[rustfmt::skip]
+    let pixels = [
+        255, 0, 0, 255, 0, 255, 0, 128,
+        0, 0, 255, 0, 255, 255, 255, 255,
+    ];
+    image::save_buffer(&input, &pixels, 2, 2, ColorType::Rgba8).unwrap();
+
+    let encoded = temp_path("encode.jxl");
+    run(&[
+        Path::new("encode"),
+        &input,
+        &encoded,
+        Path::new("--lossless"),
+    ]);
+    let output = run(&[Path::new("info"), &encoded]);
+    assert!(output.contains("Size: 2x2"));
+
+    let decoded = temp_path("encode-decoded.png");
+    run(&[Path::new("decode"), &encoded, &decoded]);
+    let image = image::open(&decoded).expect("Failed to read the output");
+    for path in [&input, &encoded, &decoded] {
+        std::fs::remove_file(path).unwrap();
+    }
+    assert_eq!(image.as_bytes(), pixels);
+}
+
+#[test]
+fn test_cli_transcode_jpeg() {
+    let jpeg = sample_path("sample.jpg");
+    let encoded = temp_path("transcode.jxl");
+    run(&[Path::new("transcode-jpeg"), &jpeg, &encoded]);
+
+    let output = run(&[Path::new("info"), &encoded]);
+    assert!(output.contains("JPEG reconstruction: true"));
+
+    let reconstructed = temp_path("transcode.jpg");
+    run(&[Path::new("decode"), &encoded, &reconstructed]);
+    let data = std::fs::read(&reconstructed).expect("Failed to read the output");
+    for path in [&encoded, &reconstructed] {
+        std::fs::remove_file(path).unwrap();
+    }
+    assert_eq!(data, std::fs::read(&jpeg).unwrap());
+}
@@ -0,0 +1,149 @@
+use std::ffi::c_void;
+
+use crate::{
+    pixel_format::{buffer_size, bytes_per_sample, row_stride},
+    version::check_encoder_version,
+    Frame, PixelFormat, Version,
+};
+use libjxl_sys::*;
+
+#[derive(Debug)]
+pub enum JxlButteraugliError {
+    /** The two images have different dimensions */
+    SizeMismatch,
+    /** The pixel format or the image size is unsupported */
+    UnsupportedValue(String),
+    /** A buffer is smaller than its dimensions and pixel format require */
+    BufferTooSmall,
+    General,
+    /** The linked libjxl is older than `MIN_VERSION` */
+    UnsupportedVersion(Version),
+}
+
+struct ButteraugliRaw {
+    api: *mut JxlButteraugliApi,
+    parallel_runner: *mut c_void,
+}
+
+impl Drop for ButteraugliRaw {
+    fn drop(&mut self) {
+        unsafe {
+            JxlThreadParallelRunnerDestroy(self.parallel_runner);
+            JxlButteraugliApiDestroy(self.api);
+        }
+    }
+}
+
+/** The outcome of `butteraugli_compare`, from which the distance scores can be read */
+pub struct ButteraugliResult {
+    result: *mut JxlButteraugliResult,
+    xsize: u32,
+    ysize: u32,
+}
+
+impl ButteraugliResult {
+    /** The largest distance over all pixels */
+    pub fn max_distance(&self) -> f32 {
+        unsafe { JxlButteraugliResultGetMaxDistance(self.result) }
+    }
+
+    /** The p-norm of the distances over all pixels, e.g. 3.0 as reported by `cjxl` */
+    pub fn distance(&self, pnorm: f32) -> f32 {
+        unsafe { JxlButteraugliResultGetDistance(self.result, pnorm) }
+    }
+
+    /** The distance of each pixel, `xsize` * `ysize` values in row-major order */
+    pub fn distmap(&self) -> Vec<f32> {
+        let mut buffer: *const f32 = std::ptr::null();
+        let mut row_stride = 0u32;
+        unsafe { JxlButteraugliResultGetDistmap(self.result, &mut buffer, &mut row_stride) };
+
+        let (xsize, ysize) = (self.xsize as usize, self.ysize as usize);
+        let mut distmap = Vec::with_capacity(xsize * ysize);
+        for y in 0..ysize {
+            let row =
+                unsafe { std::slice::from_raw_parts(buffer.add(y * row_stride as usize), xsize) };
+            distmap.extend_from_slice(row);
+        }
+        distmap
+    }
+}
+
+impl Drop for ButteraugliResult {
+    fn drop(&mut self) {
+        unsafe { JxlButteraugliResultDestroy(self.result) };
+    }
+}
+
+/**
+ * Computes the butteraugli distance of `distorted` from `reference`,
+ * where both frames hold pixels in `pixel_format` such as the ones decoded with it.
+ */
+pub fn butteraugli_compare(
+    reference: &Frame,
+    distorted: &Frame,
+    pixel_format: &PixelFormat,
+) -> Result<ButteraugliResult, JxlButteraugliError> {
+    if reference.xsize != distorted.xsize || reference.ysize != distorted.ysize {
+        return Err(JxlButteraugliError::SizeMismatch);
+    }
+
+    let (xsize, ysize) = (reference.xsize, reference.ysize);
+    let sample_size = bytes_per_sample(pixel_format.data_type).ok_or_else(|| {
+        JxlButteraugliError::UnsupportedValue(format!(
+            "Data type {} is unsupported",
+            pixel_format.data_type
+        ))
+    })?;
+    let too_large = || {
+        JxlButteraugliError::UnsupportedValue(format!(
+            "Image size {}x{} is too large",
+            xsize, ysize
+        ))
+    };
+    let row_size = (xsize as usize)
+        .checked_mul(pixel_format.num_channels as usize * sample_size)
+        .ok_or_else(too_large)?;
+    let stride = row_stride(row_size, pixel_format.align).ok_or_else(too_large)?;
+    let min_size = buffer_size(row_size, stride, ysize as usize).ok_or_else(too_large)?;
+    if reference.data.len() < min_size || distorted.data.len() < min_size {
+        return Err(JxlButteraugliError::BufferTooSmall);
+    }
+    check_encoder_version().map_err(JxlButteraugliError::UnsupportedVersion)?;
+
+    unsafe {
+        let raw = ButteraugliRaw {
+            api: JxlButteraugliApiCreate(std::ptr::null()),
+            parallel_runner: JxlThreadParallelRunnerCreate(
+                std::ptr::null(),
+                JxlThreadParallelRunnerDefaultNumWorkerThreads(),
+            ),
+        };
+        JxlButteraugliApiSetParallelRunner(
+            raw.api,
+            Some(JxlThreadParallelRunner),
+            raw.parallel_runner,
+        );
+
+        let result = JxlButteraugliCompute(
+            raw.api,
+            xsize,
+            ysize,
+            pixel_format,
+            reference.data.as_ptr() as *const c_void,
+            reference.data.len(),
+            pixel_format,
+            distorted.data.as_ptr() as *const c_void,
+            distorted.data.len(),
+        );
+        if result.is_null() {
+            return Err(JxlButteraugliError::General);
+        }
+
+        Ok(ButteraugliResult {
+            result,
+            xsize,
+            ysize,
+        })
+    }
+}
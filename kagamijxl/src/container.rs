@@ -0,0 +1,328 @@
+/*!
+ * Reads and rewrites the ISOBMFF boxes of the JPEG XL container without decoding the image,
+ * e.g. to strip metadata or to wrap a bare codestream so that metadata can be added.
+ */
+
+use std::{convert::TryFrom, io::Write};
+
+/** The contents of the `JXL ` signature box */
+const SIGNATURE_CONTENTS: [u8; 4] = [0x0d, 0x0a, 0x87, 0x0a];
+/** The `ftyp` contents: `jxl ` as the major brand, minor version 0 and `jxl ` as compatible */
+const FTYP_CONTENTS: [u8; 12] = *b"jxl \0\0\0\0jxl ";
+/** Boxes that describe the image itself, which are always kept */
+const STRUCTURAL_TYPES: [&[u8; 4]; 5] = [b"JXL ", b"ftyp", b"jxll", b"jxlc", b"jxlp"];
+/** Boxes that carry metadata that is not needed to display the image */
+const METADATA_TYPES: [&[u8; 4]; 3] = [b"Exif", b"xml ", b"jumb"];
+
+#[derive(Debug)]
+pub enum ContainerError {
+    /** The data ends in the middle of a box */
+    Truncated,
+    /** The data is not a well-formed JPEG XL container */
+    Invalid(String),
+}
+
+/** A box in a JPEG XL container, borrowing its contents from the file */
+#[derive(Debug, Clone, Copy)]
+pub struct JxlBox<'a> {
+    /** The four-character type, e.g. `jxlc` or `Exif` */
+    pub box_type: [u8; 4],
+    /** Offset of the box header from the start of the file */
+    pub offset: usize,
+    /** Size of the box including its header */
+    pub size: usize,
+    /** The box contents without the header */
+    pub contents: &'a [u8],
+}
+
+impl<'a> JxlBox<'a> {
+    /** The type of the compressed box for `brob` boxes, or the box type otherwise */
+    pub fn content_type(&self) -> [u8; 4] {
+        match (&self.box_type, self.contents.get(..4)) {
+            (b"brob", Some(inner)) => [inner[0], inner[1], inner[2], inner[3]],
+            _ => self.box_type,
+        }
+    }
+
+    /** Whether the box holds metadata, i.e. `Exif`, `xml ` or `jumb`, possibly compressed */
+    pub fn is_metadata(&self) -> bool {
+        METADATA_TYPES.contains(&&self.content_type())
+    }
+}
+
+/** Whether the data starts with a bare codestream rather than a container */
+pub fn is_codestream(data: &[u8]) -> bool {
+    data.starts_with(&[0xff, 0x0a])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ContainerError> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(ContainerError::Truncated)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_box(data: &[u8], offset: usize) -> Result<JxlBox<'_>, ContainerError> {
+    let size = read_u32(data, offset)? as u64;
+    let box_type = data
+        .get(offset + 4..offset + 8)
+        .ok_or(ContainerError::Truncated)?;
+    let box_type = [box_type[0], box_type[1], box_type[2], box_type[3]];
+
+    let (header_size, size) = match size {
+        // The box extends to the end of the file
+        0 => (8, (data.len() - offset) as u64),
+        // The actual size follows as a 64-bit integer
+        1 => {
+            let high = read_u32(data, offset + 8)? as u64;
+            let low = read_u32(data, offset + 12)? as u64;
+            (16, high << 32 | low)
+        }
+        size => (8, size),
+    };
+    if size < header_size as u64 {
+        return Err(ContainerError::Invalid(format!(
+            "Box {:?} at {} is smaller than its header",
+            String::from_utf8_lossy(&box_type),
+            offset
+        )));
+    }
+    let end = (offset as u64)
+        .checked_add(size)
+        .ok_or(ContainerError::Truncated)?;
+    if end > data.len() as u64 {
+        return Err(ContainerError::Truncated);
+    }
+
+    Ok(JxlBox {
+        box_type,
+        offset,
+        size: size as usize,
+        contents: &data[offset + header_size..end as usize],
+    })
+}
+
+/** Lists the boxes of a container, which must start with the `JXL ` signature box */
+pub fn read_boxes(data: &[u8]) -> Result<Vec<JxlBox<'_>>, ContainerError> {
+    if is_codestream(data) {
+        return Err(ContainerError::Invalid(
+            "The data is a bare codestream".to_string(),
+        ));
+    }
+
+    let mut boxes = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let jxl_box = read_box(data, offset)?;
+        if boxes.is_empty()
+            && (&jxl_box.box_type != b"JXL " || jxl_box.contents != SIGNATURE_CONTENTS)
+        {
+            return Err(ContainerError::Invalid(
+                "The signature box is missing".to_string(),
+            ));
+        }
+        offset += jxl_box.size;
+        boxes.push(jxl_box);
+    }
+    Ok(boxes)
+}
+
+/** Writes a box, using the 64-bit size field only when the contents need it */
+pub fn write_box(
+    mut writer: impl Write,
+    box_type: &[u8; 4],
+    contents: &[u8],
+) -> std::io::Result<()> {
+    let size = contents.len() as u64 + 8;
+    match u32::try_from(size) {
+        Ok(size) => {
+            writer.write_all(&size.to_be_bytes())?;
+            writer.write_all(box_type)?;
+        }
+        Err(_) => {
+            writer.write_all(&1u32.to_be_bytes())?;
+            writer.write_all(box_type)?;
+            writer.write_all(&(size + 8).to_be_bytes())?;
+        }
+    }
+    writer.write_all(contents)
+}
+
+fn write_boxes<'a>(boxes: impl IntoIterator<Item = (&'a [u8; 4], &'a [u8])>) -> Vec<u8> {
+    let mut output = Vec::new();
+    for (box_type, contents) in boxes {
+        write_box(&mut output, box_type, contents).expect("Writing to Vec should not fail");
+    }
+    output
+}
+
+/** Wraps a bare codestream in a container with the signature, `ftyp` and `jxlc` boxes */
+pub fn wrap_codestream(codestream: &[u8]) -> Vec<u8> {
+    write_boxes(vec![
+        (b"JXL ", &SIGNATURE_CONTENTS[..]),
+        (b"ftyp", &FTYP_CONTENTS[..]),
+        (b"jxlc", codestream),
+    ])
+}
+
+/**
+ * Rewrites the container with only the boxes for which `keep` returns true, except that the
+ * boxes needed to decode the image are always kept. A bare codestream is returned as is.
+ */
+pub fn retain_boxes(
+    data: &[u8],
+    mut keep: impl FnMut(&JxlBox) -> bool,
+) -> Result<Vec<u8>, ContainerError> {
+    if is_codestream(data) {
+        return Ok(data.to_vec());
+    }
+    let boxes = read_boxes(data)?;
+    Ok(write_boxes(
+        boxes
+            .iter()
+            .filter(|jxl_box| STRUCTURAL_TYPES.contains(&&jxl_box.box_type) || keep(jxl_box))
+            .map(|jxl_box| (&jxl_box.box_type, jxl_box.contents)),
+    ))
+}
+
+/**
+ * Removes `Exif`, `xml ` and `jumb` boxes, including Brotli-compressed ones.
+ * The `jbrd` box goes with them, as the JPEG file can't be reconstructed without the metadata
+ * it refers to, so the result decodes to the same pixels but is no longer a transcoded JPEG.
+ */
+pub fn strip_metadata(data: &[u8]) -> Result<Vec<u8>, ContainerError> {
+    let has_metadata = !is_codestream(data) && read_boxes(data)?.iter().any(JxlBox::is_metadata);
+    retain_boxes(data, |jxl_box| match &jxl_box.box_type {
+        b"jbrd" => !has_metadata,
+        _ => !jxl_box.is_metadata(),
+    })
+}
+
+/**
+ * Adds a box before the codestream, wrapping a bare codestream in a container first.
+ * `Exif` contents must start with the 4-byte offset to the TIFF header.
+ */
+pub fn insert_box(
+    data: &[u8],
+    box_type: &[u8; 4],
+    contents: &[u8],
+) -> Result<Vec<u8>, ContainerError> {
+    let wrapped;
+    let data = match is_codestream(data) {
+        true => {
+            wrapped = wrap_codestream(data);
+            &wrapped[..]
+        }
+        false => data,
+    };
+
+    let boxes = read_boxes(data)?;
+    let position = boxes
+        .iter()
+        .position(|jxl_box| matches!(&jxl_box.box_type, b"jxlc" | b"jxlp"))
+        .unwrap_or(boxes.len());
+
+    let mut entries: Vec<_> = boxes
+        .iter()
+        .map(|jxl_box| (&jxl_box.box_type, jxl_box.contents))
+        .collect();
+    entries.insert(position, (box_type, contents));
+    Ok(write_boxes(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        insert_box, read_boxes, retain_boxes, strip_metadata, wrap_codestream, write_box,
+        ContainerError,
+    };
+
+    const CODESTREAM: [u8; 4] = [0xff, 0x0a, 0x01, 0x02];
+
+    fn types(data: &[u8]) -> Vec<[u8; 4]> {
+        read_boxes(data)
+            .expect("Failed to read boxes")
+            .iter()
+            .map(|jxl_box| jxl_box.box_type)
+            .collect()
+    }
+
+    #[test]
+    fn wrap() {
+        let data = wrap_codestream(&CODESTREAM);
+        assert_eq!(types(&data), [*b"JXL ", *b"ftyp", *b"jxlc"]);
+
+        let boxes = read_boxes(&data).unwrap();
+        assert_eq!(boxes[2].offset, 32);
+        assert_eq!(boxes[2].size, 12);
+        assert_eq!(boxes[2].contents, CODESTREAM);
+    }
+
+    #[test]
+    fn sizes() {
+        let mut data = wrap_codestream(&CODESTREAM);
+        // A 64-bit sized box followed by one that extends to the end
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(b"xml ");
+        data.extend_from_slice(&18u64.to_be_bytes());
+        data.extend_from_slice(b"<x");
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(b"Exif");
+        data.extend_from_slice(b"rest");
+
+        let boxes = read_boxes(&data).unwrap();
+        assert_eq!(boxes[3].contents, b"<x");
+        assert_eq!(boxes[4].contents, b"rest");
+        assert_eq!(boxes[4].size, 12);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = wrap_codestream(&CODESTREAM);
+        assert!(matches!(
+            read_boxes(&data[..data.len() - 1]),
+            Err(ContainerError::Truncated)
+        ));
+        assert!(matches!(
+            read_boxes(&CODESTREAM),
+            Err(ContainerError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn size_overflow() {
+        let mut data = wrap_codestream(&CODESTREAM);
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(b"xml ");
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(matches!(read_boxes(&data), Err(ContainerError::Truncated)));
+    }
+
+    #[test]
+    fn insert() {
+        let data = insert_box(&CODESTREAM, b"Exif", &[0, 0, 0, 0]).unwrap();
+        assert_eq!(types(&data), [*b"JXL ", *b"ftyp", *b"Exif", *b"jxlc"]);
+    }
+
+    #[test]
+    fn strip() {
+        let mut data = wrap_codestream(&CODESTREAM);
+        write_box(&mut data, b"Exif", &[0, 0, 0, 0]).unwrap();
+        write_box(&mut data, b"brob", b"xml compressed").unwrap();
+        write_box(&mut data, b"jbrd", &[1]).unwrap();
+
+        let stripped = strip_metadata(&data).unwrap();
+        assert_eq!(types(&stripped), [*b"JXL ", *b"ftyp", *b"jxlc"]);
+
+        // Without metadata the reconstruction data stays valid
+        let mut data = wrap_codestream(&CODESTREAM);
+        write_box(&mut data, b"jbrd", &[1]).unwrap();
+        let stripped = strip_metadata(&data).unwrap();
+        assert_eq!(types(&stripped), [*b"JXL ", *b"ftyp", *b"jxlc", *b"jbrd"]);
+
+        let stripped = retain_boxes(&data, |_| false).unwrap();
+        assert_eq!(types(&stripped), [*b"JXL ", *b"ftyp", *b"jxlc"]);
+
+        assert_eq!(strip_metadata(&CODESTREAM).unwrap(), CODESTREAM);
+    }
+}
@@ -1,11 +1,23 @@
 use std::{
+    any::Any,
     ffi::c_void,
     fmt::Debug,
     fs::File,
     io::{BufRead, BufReader},
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use crate::{contiguous_buffer::ContiguousBuffer, BasicInfo};
+use crate::{
+    contiguous_buffer::ContiguousBuffer,
+    downsample::{Downsampler, MAX_FACTOR},
+    pixel_format::bytes_per_pixel,
+    version::check_decoder_version,
+    BasicInfo, BlendInfo, Version,
+};
 use libjxl_sys::*;
 
 #[derive(Debug)]
@@ -13,7 +25,11 @@ pub enum JxlDecodeError {
     AllocationFailed,
     InputNotComplete,
     AlreadyFinished,
+    BufferTooSmall,
     General,
+    UnsupportedValue(String),
+    /** The linked libjxl is older than `MIN_VERSION` */
+    UnsupportedVersion(Version),
 }
 
 macro_rules! try_dec_fatal {
@@ -74,11 +90,19 @@ fn prepare_frame(
 
     name_vec.pop(); // The string ends with null which is redundant in Rust
 
+    let layer_info = &header.layer_info;
     let frame = Frame {
         name: String::from_utf8_lossy(&name_vec[..]).to_string(),
         duration: header.duration,
         timecode: header.timecode,
         is_last: header.is_last != 0,
+        have_crop: layer_info.have_crop != 0,
+        crop_x0: layer_info.crop_x0,
+        crop_y0: layer_info.crop_y0,
+        xsize: layer_info.xsize,
+        ysize: layer_info.ysize,
+        blend_info: layer_info.blend_info,
+        save_as_reference: layer_info.save_as_reference,
         ..Default::default()
     };
     result.frames.push(frame);
@@ -114,6 +138,18 @@ fn prepare_preview_out_buffer(
     Ok(())
 }
 
+/** The size of tightly packed pixels, or `AllocationFailed` when it doesn't fit in memory */
+fn packed_size(
+    xsize: u32,
+    ysize: u32,
+    pixel_format: &JxlPixelFormat,
+) -> Result<usize, JxlDecodeError> {
+    (xsize as usize)
+        .checked_mul(ysize as usize)
+        .and_then(|pixels| pixels.checked_mul(bytes_per_pixel(pixel_format)))
+        .ok_or(JxlDecodeError::AllocationFailed)
+}
+
 fn prepare_image_out_buffer(
     dec: *mut JxlDecoderStruct,
     result: &mut DecodeProgress,
@@ -126,16 +162,57 @@ fn prepare_image_out_buffer(
         &mut buffer_size
     ));
 
-    assert_eq!(
-        buffer_size,
-        (result.basic_info.xsize * result.basic_info.ysize * 4) as usize
-    );
+    if let Some(out_buffer) = result.out_buffer.take() {
+        if out_buffer.size < buffer_size {
+            return Err(JxlDecodeError::BufferTooSmall);
+        }
+        try_dec_fatal!(JxlDecoderSetImageOutBuffer(
+            dec,
+            pixel_format,
+            out_buffer.buffer,
+            out_buffer.size,
+        ));
+        return Ok(());
+    }
 
-    let buffer = &mut result
+    if let Some(target) = &result.callback_target {
+        try_dec_fatal!(JxlDecoderSetImageOutCallback(
+            dec,
+            pixel_format,
+            Some(call_image_out_callback),
+            &**target as *const CallbackTarget as *mut _,
+        ));
+        return Ok(());
+    }
+
+    let frame = result
         .frames
         .last_mut()
-        .expect("Frames vector is unexpectedly empty")
-        .data;
+        .expect("Frames vector is unexpectedly empty");
+
+    // Frames are as large as the canvas unless coalescing is disabled
+    if buffer_size != packed_size(frame.xsize, frame.ysize, pixel_format)? {
+        return Err(JxlDecodeError::General);
+    }
+
+    if result.crop.is_some() || result.downsample.is_some() {
+        let target = Box::new(RegionTarget::new(
+            frame,
+            result.crop,
+            result.downsample,
+            pixel_format,
+        )?);
+        try_dec_fatal!(JxlDecoderSetImageOutCallback(
+            dec,
+            pixel_format,
+            Some(write_region_row),
+            &*target as *const RegionTarget as *mut _,
+        ));
+        result.region_target = Some(target);
+        return Ok(());
+    }
+
+    let buffer = &mut frame.data;
 
     buffer.resize(buffer_size as usize, 0);
     try_dec_fatal!(JxlDecoderSetImageOutBuffer(
@@ -147,6 +224,248 @@ fn prepare_image_out_buffer(
     Ok(())
 }
 
+/** What `write_region_row` does with the pixels inside the region */
+enum RegionOutput {
+    /** Copies them into the frame data */
+    Copy(*mut u8),
+    /**
+     * Accumulates them to shrink the frame. Pixels rendered by `flush_image` go to `flushed`
+     * instead of `decoded`, as libjxl renders them again once their groups are complete.
+     */
+    Downsample {
+        decoded: Downsampler,
+        flushed: Downsampler,
+        flushing: AtomicBool,
+    },
+}
+
+/** Where `write_region_row` writes the pixels inside the region of a frame to */
+struct RegionTarget {
+    pixel_size: usize,
+    x0: usize,
+    y0: usize,
+    xsize: usize,
+    ysize: usize,
+    output: RegionOutput,
+}
+
+impl RegionTarget {
+    fn new(
+        frame: &mut Frame,
+        crop: Option<Rect>,
+        downsample: Option<u32>,
+        pixel_format: &JxlPixelFormat,
+    ) -> Result<Self, JxlDecodeError> {
+        let (mut x0, mut y0) = (0, 0);
+        if let Some(crop) = crop {
+            // Clamp the region so that it never exceeds the frame
+            x0 = crop.x0.min(frame.xsize);
+            y0 = crop.y0.min(frame.ysize);
+            frame.xsize = crop.xsize.min(frame.xsize - x0);
+            frame.ysize = crop.ysize.min(frame.ysize - y0);
+        }
+        let (xsize, ysize) = (frame.xsize as usize, frame.ysize as usize);
+
+        let output = match downsample {
+            Some(factor) => {
+                // Samples are averaged as bytes, which is what `default_pixel_format` gives
+                debug_assert_eq!(pixel_format.data_type, JXL_TYPE_UINT8);
+                let num_channels = pixel_format.num_channels as usize;
+                let downsampler = || {
+                    Downsampler::new(xsize, ysize, num_channels, factor as usize)
+                        .ok_or(JxlDecodeError::AllocationFailed)
+                };
+                let decoded = downsampler()?;
+                // The data is filled once the frame is complete or flushed
+                frame.xsize = decoded.new_xsize() as u32;
+                frame.ysize = decoded.new_ysize() as u32;
+                RegionOutput::Downsample {
+                    decoded,
+                    flushed: downsampler()?,
+                    flushing: AtomicBool::new(false),
+                }
+            }
+            None => {
+                let buffer = &mut frame.data;
+                buffer.resize(packed_size(frame.xsize, frame.ysize, pixel_format)?, 0);
+                RegionOutput::Copy(buffer.as_mut_ptr())
+            }
+        };
+
+        Ok(RegionTarget {
+            pixel_size: bytes_per_pixel(pixel_format),
+            x0: x0 as usize,
+            y0: y0 as usize,
+            xsize,
+            ysize,
+            output,
+        })
+    }
+}
+
+unsafe extern "C" fn write_region_row(
+    opaque: *mut c_void,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+) {
+    let target = &*(opaque as *const RegionTarget);
+    if y < target.y0 || y >= target.y0 + target.ysize {
+        return;
+    }
+
+    let start = x.max(target.x0);
+    let end = (x + num_pixels).min(target.x0 + target.xsize);
+    if start >= end {
+        return;
+    }
+
+    let src = (pixels as *const u8).add((start - x) * target.pixel_size);
+    let len = (end - start) * target.pixel_size;
+    let (x, y) = (start - target.x0, y - target.y0);
+    match &target.output {
+        RegionOutput::Copy(buffer) => {
+            // Each call writes a distinct part of a row, so this is safe even with multiple threads
+            let dst = buffer.add((y * target.xsize + x) * target.pixel_size);
+            std::ptr::copy_nonoverlapping(src, dst, len);
+        }
+        RegionOutput::Downsample {
+            decoded,
+            flushed,
+            flushing,
+        } => {
+            let downsampler = match flushing.load(Ordering::Relaxed) {
+                true => flushed,
+                false => decoded,
+            };
+            downsampler.add(x, y, std::slice::from_raw_parts(src, len));
+        }
+    }
+}
+
+/** Renders the current frame from the input so far, including its incomplete groups */
+fn flush_image(result: &mut DecodeProgress) -> JxlDecoderStatus {
+    let dec = result.raw.decoder;
+    let (flushed, flushing) = match result.region_target.as_deref() {
+        Some(RegionTarget {
+            output: RegionOutput::Downsample {
+                flushed, flushing, ..
+            },
+            ..
+        }) => (flushed, flushing),
+        _ => return unsafe { JxlDecoderFlushImage(dec) },
+    };
+
+    flushed.clear();
+    flushing.store(true, Ordering::Relaxed);
+    let status = unsafe { JxlDecoderFlushImage(dec) };
+    flushing.store(false, Ordering::Relaxed);
+
+    if let (Some(target), Some(frame)) = (&result.region_target, result.frames.last_mut()) {
+        if let RegionOutput::Downsample {
+            decoded, flushed, ..
+        } = &target.output
+        {
+            frame.data = decoded.average(Some(flushed));
+        }
+    }
+    status
+}
+
+/** Writes the shrunk pixels of the complete frame and releases its region target */
+fn finish_region(result: &mut DecodeProgress) {
+    let target = match result.region_target.take() {
+        Some(target) => target,
+        None => return,
+    };
+    if let (RegionOutput::Downsample { decoded, .. }, Some(frame)) =
+        (&target.output, result.frames.last_mut())
+    {
+        frame.data = decoded.average(None);
+    }
+}
+
+/**
+ * Receives `(x, y, num_pixels, pixels)` for each decoded part of a row.
+ * It can be called from multiple threads at once, each time with different pixels.
+ */
+pub type ImageOutCallback = dyn Fn(usize, usize, usize, &[u8]) + Send + Sync;
+
+struct CallbackTarget {
+    callback: Arc<ImageOutCallback>,
+    pixel_size: usize,
+    /** The first panic of the callback, which must not unwind through libjxl */
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl CallbackTarget {
+    /** Continues unwinding from the panic the callback raised, if any */
+    fn resume_panic(&self) {
+        let payload = self
+            .panic
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+        if let Some(payload) = payload {
+            resume_unwind(payload);
+        }
+    }
+}
+
+unsafe extern "C" fn call_image_out_callback(
+    opaque: *mut c_void,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+) {
+    let target = &*(opaque as *const CallbackTarget);
+    let pixels = std::slice::from_raw_parts(pixels as *const u8, num_pixels * target.pixel_size);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        (target.callback)(x, y, num_pixels, pixels)
+    }));
+    if let Err(payload) = result {
+        target
+            .panic
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get_or_insert(payload);
+    }
+}
+
+fn read_frame_progression(result: &mut DecodeProgress) -> Result<(), JxlDecodeError> {
+    let dec = result.raw.decoder;
+    let factor = match result.downsample {
+        Some(factor) => factor,
+        None => return Ok(()),
+    };
+
+    let frame = result
+        .frames
+        .last()
+        .expect("Frames vector is unexpectedly empty");
+
+    // Skipping the rest of a frame is only safe when no later frame depends on it
+    if !frame.is_last {
+        return Ok(());
+    }
+
+    let ratio = unsafe { JxlDecoderGetIntendedDownsamplingRatio(dec) };
+    if ratio > factor as usize {
+        return Ok(());
+    }
+
+    // The flushed image is upsampled to the full size, which the region target shrinks again
+    let status = flush_image(result);
+    if status != JXL_DEC_SUCCESS {
+        panic!("A fatal error occurred in kagamijxl::Decoder");
+    }
+    try_dec_fatal!(JxlDecoderSkipCurrentFrame(dec));
+    result.region_target = None;
+    Ok(())
+}
+
 fn decode_loop(
     progress: &mut DecodeProgress,
     data: impl BufRead,
@@ -162,6 +481,9 @@ fn decode_loop(
 
     loop {
         let status = unsafe { JxlDecoderProcessInput(dec) };
+        if let Some(target) = &progress.callback_target {
+            target.resume_panic();
+        }
 
         match status {
             JXL_DEC_NEED_MORE_INPUT => {
@@ -193,7 +515,10 @@ fn decode_loop(
             // Get the output buffer
             JXL_DEC_NEED_IMAGE_OUT_BUFFER => prepare_image_out_buffer(dec, progress, pixel_format)?,
 
+            JXL_DEC_FRAME_PROGRESSION => read_frame_progression(progress)?,
+
             JXL_DEC_FULL_IMAGE => {
+                finish_region(progress);
                 if stop_on_frame && !progress.frames.last().unwrap().is_last {
                     let remaining = unsafe { JxlDecoderReleaseInput(dec) };
                     let consumed = buffer.len() - remaining;
@@ -230,6 +555,9 @@ fn get_event_subscription_flags(dec: &Decoder) -> JxlDecoderStatus {
     }
     if !dec.no_full_image && !dec.no_full_frame {
         flags |= JXL_DEC_FULL_IMAGE;
+        if dec.downsample.is_some() {
+            flags |= JXL_DEC_FRAME_PROGRESSION;
+        }
     }
     flags
 }
@@ -253,27 +581,71 @@ fn prepare_decoder(
     Ok(())
 }
 
-pub fn decode_oneshot(data: impl BufRead, dec: &Decoder) -> Result<DecodeProgress, JxlDecodeError> {
+fn default_pixel_format() -> JxlPixelFormat {
+    // TODO: Support different pixel format
+    // Not sure how to type the output vector properly
+    JxlPixelFormat {
+        num_channels: 4,
+        data_type: JXL_TYPE_UINT8,
+        endianness: JXL_NATIVE_ENDIAN,
+        align: 0,
+    }
+}
+
+fn create_progress(dec: &Decoder) -> Result<DecodeProgress, JxlDecodeError> {
+    if let Some(factor) = dec.downsample {
+        if !(1..=MAX_FACTOR).contains(&factor) {
+            return Err(JxlDecodeError::UnsupportedValue(format!(
+                "Downsampling factor {} is unsupported",
+                factor
+            )));
+        }
+    }
+
     let mut progress = DecodeProgress::new(dec.keep_orientation)?;
 
+    if let Some(callback) = &dec.image_out_callback {
+        progress.callback_target = Some(Box::new(CallbackTarget {
+            callback: callback.clone(),
+            pixel_size: bytes_per_pixel(&default_pixel_format()),
+            panic: Mutex::new(None),
+        }));
+    } else {
+        progress.crop = dec.crop;
+        // A factor of 1 leaves the frames as they are
+        progress.downsample = dec.downsample.filter(|factor| *factor > 1);
+    }
+
+    if progress.downsample.is_some() {
+        try_dec_fatal!(JxlDecoderSetProgressiveDetail(
+            progress.raw.decoder,
+            kPasses
+        ));
+    }
+
+    if let Some(coalescing) = dec.coalescing {
+        try_dec_fatal!(JxlDecoderSetCoalescing(
+            progress.raw.decoder,
+            coalescing as i32
+        ));
+    }
+
     let event_flags = get_event_subscription_flags(dec);
     try_dec_fatal!(JxlDecoderSubscribeEvents(
         progress.raw.decoder,
         event_flags as i32
     ));
 
-    // TODO: Support different pixel format
-    // Not sure how to type the output vector properly
-    let pixel_format = JxlPixelFormat {
-        num_channels: 4,
-        data_type: JXL_TYPE_UINT8,
-        endianness: JXL_NATIVE_ENDIAN,
-        align: 0,
-    };
+    Ok(progress)
+}
+
+pub fn decode_oneshot(data: impl BufRead, dec: &Decoder) -> Result<DecodeProgress, JxlDecodeError> {
+    let mut progress = create_progress(dec)?;
+
     decode_loop(
         &mut progress,
         data,
-        &pixel_format,
+        &default_pixel_format(),
         dec.stop_on_frame,
         dec.allow_partial,
     )?;
@@ -281,9 +653,121 @@ pub fn decode_oneshot(data: impl BufRead, dec: &Decoder) -> Result<DecodeProgres
     Ok(progress)
 }
 
+fn decode_into_buffer(
+    data: impl BufRead,
+    dec: &Decoder,
+    out_buffer: OutBuffer,
+    pixel_format: &JxlPixelFormat,
+) -> Result<DecodeProgress, JxlDecodeError> {
+    let mut progress = create_progress(dec)?;
+
+    // The caller buffer takes precedence over the options that change the output
+    progress.crop = None;
+    progress.downsample = None;
+    progress.callback_target = None;
+    progress.out_buffer = Some(out_buffer);
+
+    // The first frame must be complete before returning, or the decoder would keep the buffer
+    decode_loop(&mut progress, data, pixel_format, true, false)?;
+    progress.out_buffer = None;
+
+    Ok(progress)
+}
+
+/**
+ * Reconstructs the original JPEG file from a JXL image transcoded from it,
+ * or returns `None` when the image holds no JPEG reconstruction data.
+ */
+pub fn reconstruct_jpeg(data: &[u8]) -> Result<Option<Vec<u8>>, JxlDecodeError> {
+    let progress = DecodeProgress::new(None)?;
+    let dec = progress.raw.decoder;
+
+    let event_flags = JXL_DEC_JPEG_RECONSTRUCTION | JXL_DEC_FULL_IMAGE;
+    try_dec_fatal!(JxlDecoderSubscribeEvents(dec, event_flags as i32));
+    try_dec_fatal!(JxlDecoderSetInput(dec, data.as_ptr(), data.len()));
+    unsafe { JxlDecoderCloseInput(dec) };
+
+    let mut jpeg: Vec<u8> = Vec::new();
+    loop {
+        let status = unsafe { JxlDecoderProcessInput(dec) };
+        match status {
+            JXL_DEC_JPEG_RECONSTRUCTION => {
+                jpeg.resize(data.len().max(64), 0);
+                try_dec_fatal!(JxlDecoderSetJPEGBuffer(dec, jpeg.as_mut_ptr(), jpeg.len()));
+            }
+            JXL_DEC_JPEG_NEED_MORE_OUTPUT => {
+                let used = jpeg.len() - unsafe { JxlDecoderReleaseJPEGBuffer(dec) };
+                jpeg.resize(jpeg.len() * 2, 0);
+                try_dec_fatal!(JxlDecoderSetJPEGBuffer(
+                    dec,
+                    jpeg.as_mut_ptr().add(used),
+                    jpeg.len() - used
+                ));
+            }
+            // Pixels are requested only when there is nothing to reconstruct
+            JXL_DEC_NEED_IMAGE_OUT_BUFFER => return Ok(None),
+            JXL_DEC_FULL_IMAGE => continue,
+            JXL_DEC_SUCCESS => break,
+
+            JXL_DEC_NEED_MORE_INPUT => return Err(JxlDecodeError::InputNotComplete),
+            JXL_DEC_ERROR => return Err(JxlDecodeError::General),
+            _ => panic!("Unexpected JXL decoding status found: {}", status),
+        }
+    }
+
+    let remaining = unsafe { JxlDecoderReleaseJPEGBuffer(dec) };
+    jpeg.truncate(jpeg.len() - remaining);
+    Ok(Some(jpeg))
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for f32 {}
+}
+
+/** A sample type that `Decoder::decode_into` can write */
+pub trait Sample: Copy + sealed::Sealed {
+    const DATA_TYPE: JxlDataType;
+}
+
+impl Sample for u8 {
+    const DATA_TYPE: JxlDataType = JXL_TYPE_UINT8;
+}
+
+impl Sample for u16 {
+    const DATA_TYPE: JxlDataType = JXL_TYPE_UINT16;
+}
+
+impl Sample for f32 {
+    const DATA_TYPE: JxlDataType = JXL_TYPE_FLOAT;
+}
+
+struct OutBuffer {
+    buffer: *mut c_void,
+    size: usize,
+}
+
 #[derive(Default)]
 pub struct Decoder {
     pub keep_orientation: Option<bool>,
+    /** Returns each frame as an individual layer instead of a composited canvas when set to false */
+    pub coalescing: Option<bool>,
+    /** Reads only the given region of each frame into `Frame::data` when set */
+    pub crop: Option<Rect>,
+    /**
+     * Shrinks each frame by the given factor when set, e.g. 8 for 1:8 thumbnails.
+     * Pixels are averaged as they are decoded, so frames are never held at their full size,
+     * and the last frame stops decoding at the earliest progressive pass that has enough detail.
+     * Factors other than 1 to 256 fail with `JxlDecodeError::UnsupportedValue`.
+     */
+    pub downsample: Option<u32>,
+    /**
+     * Passes decoded pixels to the callback as they are produced instead of storing them in
+     * `Frame::data` when set, in which case `crop` and `downsample` are ignored
+     */
+    pub image_out_callback: Option<Arc<ImageOutCallback>>,
 
     // pub pixel_format: Option<JxlPixelFormat>,
     /** Reads color profile into `DecodeProgres::color_profile` when set to true */
@@ -319,6 +803,64 @@ impl Decoder {
     pub fn decode_buffer(&self, buffer: impl BufRead) -> Result<DecodeProgress, JxlDecodeError> {
         decode_oneshot(buffer, self)
     }
+
+    /**
+     * Reads the first frame into `buffer` with `num_channels` interleaved samples per pixel,
+     * padding each row to a multiple of `align` bytes, or returns `JxlDecodeError::BufferTooSmall`.
+     * `num_channels` must be 1 to 4, i.e. gray, gray with alpha, RGB or RGBA.
+     * `crop`, `downsample` and `image_out_callback` don't apply to the buffer, and later frames
+     * can be read into `Frame::data` as usual by `DecodeProgress::proceed`.
+     */
+    pub fn decode_into<T: Sample>(
+        &self,
+        data: &[u8],
+        buffer: &mut [T],
+        num_channels: u32,
+        align: usize,
+    ) -> Result<DecodeProgress, JxlDecodeError> {
+        if !(1..=4).contains(&num_channels) {
+            return Err(JxlDecodeError::UnsupportedValue(format!(
+                "Number of channels {} is unsupported",
+                num_channels
+            )));
+        }
+
+        let pixel_format = JxlPixelFormat {
+            num_channels,
+            data_type: T::DATA_TYPE,
+            endianness: JXL_NATIVE_ENDIAN,
+            align,
+        };
+        let out_buffer = OutBuffer {
+            buffer: buffer.as_mut_ptr() as *mut _,
+            size: std::mem::size_of_val(buffer),
+        };
+        decode_into_buffer(data, self, out_buffer, &pixel_format)
+    }
+
+    /** Same as `decode_into`, but with the pixel format of `buffer` given as is */
+    #[cfg(feature = "image")]
+    pub(crate) fn decode_into_format(
+        &self,
+        data: &[u8],
+        buffer: &mut [u8],
+        pixel_format: &JxlPixelFormat,
+    ) -> Result<DecodeProgress, JxlDecodeError> {
+        let out_buffer = OutBuffer {
+            buffer: buffer.as_mut_ptr() as *mut _,
+            size: buffer.len(),
+        };
+        decode_into_buffer(data, self, out_buffer, pixel_format)
+    }
+}
+
+/** A rectangular region in pixels */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rect {
+    pub x0: u32,
+    pub y0: u32,
+    pub xsize: u32,
+    pub ysize: u32,
 }
 
 struct DecodeRaw {
@@ -338,6 +880,11 @@ impl Drop for DecodeRaw {
 pub struct DecodeProgress {
     raw: DecodeRaw,
     unread_buffer: Option<Vec<u8>>,
+    crop: Option<Rect>,
+    region_target: Option<Box<RegionTarget>>,
+    downsample: Option<u32>,
+    callback_target: Option<Box<CallbackTarget>>,
+    out_buffer: Option<OutBuffer>,
 
     is_partial: bool,
 
@@ -361,6 +908,8 @@ impl Debug for DecodeProgress {
 
 impl DecodeProgress {
     pub fn new(keep_orientation: Option<bool>) -> Result<DecodeProgress, JxlDecodeError> {
+        check_decoder_version().map_err(JxlDecodeError::UnsupportedVersion)?;
+
         let decoder = unsafe { JxlDecoderCreate(std::ptr::null()) };
         let parallel_runner = unsafe {
             JxlThreadParallelRunnerCreate(
@@ -377,6 +926,11 @@ impl DecodeProgress {
                 parallel_runner,
             },
             unread_buffer: None,
+            crop: None,
+            region_target: None,
+            downsample: None,
+            callback_target: None,
+            out_buffer: None,
 
             is_partial: true,
 
@@ -401,20 +955,21 @@ impl DecodeProgress {
             return Err(JxlDecodeError::AlreadyFinished);
         }
 
-        // TODO: Support different pixel format
-        // Not sure how to type the output vector properly
-        let pixel_format = JxlPixelFormat {
-            num_channels: 4,
-            data_type: JXL_TYPE_UINT8,
-            endianness: JXL_NATIVE_ENDIAN,
-            align: 0,
-        };
-        decode_loop(self, data, &pixel_format, stop_on_frame, allow_partial)?;
+        decode_loop(
+            self,
+            data,
+            &default_pixel_format(),
+            stop_on_frame,
+            allow_partial,
+        )?;
         Ok(())
     }
 
     pub fn flush(&mut self) {
-        unsafe { JxlDecoderFlushImage(self.raw.decoder) };
+        flush_image(self);
+        if let Some(target) = &self.callback_target {
+            target.resume_panic();
+        }
     }
 }
 
@@ -425,6 +980,21 @@ pub struct Frame {
     pub timecode: u32,
     pub is_last: bool,
 
-    /** Can be empty when `no_full_frame` is specified */
+    /** Can be true only when `coalescing` is disabled */
+    pub have_crop: bool,
+    /** Horizontal offset of the frame on the canvas, which can be negative */
+    pub crop_x0: i32,
+    /** Vertical offset of the frame on the canvas, which can be negative */
+    pub crop_y0: i32,
+    /** Width of `data`, which can differ from the canvas width depending on `Decoder` options */
+    pub xsize: u32,
+    /** Height of `data`, which can differ from the canvas height depending on `Decoder` options */
+    pub ysize: u32,
+    /** How the frame is blended onto the canvas, meaningful only when `coalescing` is disabled */
+    pub blend_info: BlendInfo,
+    /** The reference slot this frame is saved in for later frames to blend against */
+    pub save_as_reference: u32,
+
+    /** Can be empty when `no_full_frame`, `image_out_callback` or `decode_into` is used */
     pub data: Vec<u8>,
 }
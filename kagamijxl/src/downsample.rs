@@ -0,0 +1,163 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/** The largest factor, which keeps the sums of a block well within `u32` */
+pub const MAX_FACTOR: u32 = 256;
+
+/**
+ * Shrinks 8-bit interleaved pixels by averaging each `factor` x `factor` block, accumulating
+ * the pixels as they arrive so that the image is never held at its full size.
+ * Blocks on the right and bottom edges may be smaller when the size is not divisible.
+ */
+pub struct Downsampler {
+    xsize: usize,
+    ysize: usize,
+    num_channels: usize,
+    factor: usize,
+    new_xsize: usize,
+    new_ysize: usize,
+    sums: Vec<AtomicU32>,
+}
+
+impl Downsampler {
+    /** Returns `None` when the shrunk image doesn't fit in memory */
+    pub fn new(xsize: usize, ysize: usize, num_channels: usize, factor: usize) -> Option<Self> {
+        let new_xsize = xsize.div_ceil(factor);
+        let new_ysize = ysize.div_ceil(factor);
+        let len = new_xsize
+            .checked_mul(new_ysize)?
+            .checked_mul(num_channels)?;
+        Some(Downsampler {
+            xsize,
+            ysize,
+            num_channels,
+            factor,
+            new_xsize,
+            new_ysize,
+            sums: (0..len).map(|_| AtomicU32::new(0)).collect(),
+        })
+    }
+
+    pub fn new_xsize(&self) -> usize {
+        self.new_xsize
+    }
+
+    pub fn new_ysize(&self) -> usize {
+        self.new_ysize
+    }
+
+    /** Adds the pixels of a row starting at `(x, y)`, which can be called from multiple threads */
+    pub fn add(&self, x: usize, y: usize, pixels: &[u8]) {
+        let row_start = y / self.factor * self.new_xsize;
+        for (index, pixel) in pixels.chunks_exact(self.num_channels).enumerate() {
+            let start = (row_start + (x + index) / self.factor) * self.num_channels;
+            for (sum, value) in self.sums[start..].iter().zip(pixel) {
+                sum.fetch_add(*value as u32, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        self.sums
+            .iter()
+            .for_each(|sum| sum.store(0, Ordering::Relaxed));
+    }
+
+    /** Averages the pixels added so far, together with the ones added to `extra` if given */
+    pub fn average(&self, extra: Option<&Downsampler>) -> Vec<u8> {
+        let mut result = vec![0u8; self.sums.len()];
+        for (index, pixel) in result.chunks_exact_mut(self.num_channels).enumerate() {
+            let (new_x, new_y) = (index % self.new_xsize, index / self.new_xsize);
+            let block_xsize = self.xsize.min((new_x + 1) * self.factor) - new_x * self.factor;
+            let block_ysize = self.ysize.min((new_y + 1) * self.factor) - new_y * self.factor;
+            let count = (block_xsize * block_ysize) as u32;
+
+            for (channel, value) in pixel.iter_mut().enumerate() {
+                let start = index * self.num_channels + channel;
+                let mut sum = self.sums[start].load(Ordering::Relaxed);
+                if let Some(extra) = extra {
+                    sum += extra.sums[start].load(Ordering::Relaxed);
+                }
+                // Round to nearest
+                *value = ((sum + count / 2) / count) as u8;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Downsampler;
+
+    fn downsample(
+        data: &[u8],
+        xsize: usize,
+        ysize: usize,
+        num_channels: usize,
+        factor: usize,
+    ) -> (Vec<u8>, usize, usize) {
+        let downsampler = Downsampler::new(xsize, ysize, num_channels, factor).unwrap();
+        for (y, row) in data.chunks_exact(xsize * num_channels).enumerate() {
+            downsampler.add(0, y, row);
+        }
+        (
+            downsampler.average(None),
+            downsampler.new_xsize(),
+            downsampler.new_ysize(),
+        )
+    }
+
+    #[test]
+    fn average_blocks() {
+        #[rustfmt::skip]
+        let data = [
+            0, 10, 20, 30,
+            40, 50, 60, 70,
+        ];
+        let (result, xsize, ysize) = downsample(&data, 4, 2, 1, 2);
+        assert_eq!((xsize, ysize), (2, 1));
+        assert_eq!(result, [25, 45]);
+    }
+
+    #[test]
+    fn partial_edge_blocks() {
+        #[rustfmt::skip]
+        let data = [
+            0, 0, 10, 0, 0, 20,
+            0, 0, 30, 0, 0, 40,
+        ];
+        let (result, xsize, ysize) = downsample(&data, 3, 2, 2, 2);
+        assert_eq!((xsize, ysize), (2, 1));
+        assert_eq!(result, [10, 0, 0, 30]);
+    }
+
+    #[test]
+    fn factor_one() {
+        let data = [1, 2, 3, 4, 5, 6];
+        let (result, xsize, ysize) = downsample(&data, 2, 1, 3, 1);
+        assert_eq!((xsize, ysize), (2, 1));
+        assert_eq!(result, data);
+    }
+
+    #[test]
+    fn partial_rows() {
+        let downsampler = Downsampler::new(4, 1, 1, 2).unwrap();
+        // Parts of a row can arrive separately and in any order
+        downsampler.add(1, 0, &[20, 30]);
+        downsampler.add(3, 0, &[40]);
+        downsampler.add(0, 0, &[10]);
+        assert_eq!(downsampler.average(None), [15, 35]);
+    }
+
+    #[test]
+    fn extra_sums() {
+        let decoded = Downsampler::new(2, 2, 1, 2).unwrap();
+        let flushed = Downsampler::new(2, 2, 1, 2).unwrap();
+        decoded.add(0, 0, &[10, 20]);
+        flushed.add(0, 1, &[30, 40]);
+        assert_eq!(decoded.average(Some(&flushed)), [25]);
+
+        flushed.clear();
+        assert_eq!(decoded.average(Some(&flushed)), [8]);
+    }
+}
@@ -1,5 +1,11 @@
 use std::{ffi::c_void, os::raw::c_int};
 
+use crate::{
+    decode::reconstruct_jpeg,
+    pixel_format::{buffer_size, bytes_per_sample, interleave, row_stride},
+    version::check_encoder_version,
+    BlendInfo, Decoder, FrameSettings, PixelFormat, Version,
+};
 use libjxl_sys::*;
 
 macro_rules! try_enc {
@@ -21,6 +27,11 @@ macro_rules! try_enc_fatal {
 #[derive(Debug)]
 pub enum JxlEncodeError {
     UnsupportedValue(String),
+    BufferTooSmall,
+    /** The JPEG file reconstructed from the output differs from the input */
+    VerificationFailed,
+    /** The linked libjxl is older than `MIN_VERSION` */
+    UnsupportedVersion(Version),
 }
 
 unsafe fn encode_loop(enc: *mut JxlEncoderStruct) -> Vec<u8> {
@@ -53,7 +64,7 @@ fn prepare_encoder(
     enc_raw: *mut JxlEncoderStruct,
     basic_info: &JxlBasicInfo,
     runner: *mut c_void,
-    frame: &dyn InputFrame,
+    frames: &[(&dyn InputFrame, Option<&FrameInfo>)],
 ) -> Result<(), JxlEncodeError> {
     try_enc_fatal!(JxlEncoderSetParallelRunner(
         enc_raw,
@@ -63,61 +74,437 @@ fn prepare_encoder(
 
     try_enc_fatal!(JxlEncoderSetBasicInfo(enc_raw, basic_info));
 
-    let mut color_encoding = JxlColorEncoding::default();
-    unsafe { JxlColorEncodingSetToSRGB(&mut color_encoding, 0) };
-    try_enc_fatal!(JxlEncoderSetColorEncoding(enc_raw, &color_encoding));
+    if let Some(color_profile) = &enc.color_profile {
+        try_enc!(
+            JxlEncoderSetICCProfile(enc_raw, color_profile.as_ptr(), color_profile.len()),
+            JxlEncodeError::UnsupportedValue("Color profile is unsupported".to_string())
+        );
+    } else {
+        let color_encoding = enc.color_encoding.unwrap_or_else(|| {
+            let mut color_encoding = JxlColorEncoding::default();
+            let is_gray = basic_info.num_color_channels == 1;
+            unsafe { JxlColorEncodingSetToSRGB(&mut color_encoding, is_gray as _) };
+            color_encoding
+        });
+        try_enc!(
+            JxlEncoderSetColorEncoding(enc_raw, &color_encoding),
+            JxlEncodeError::UnsupportedValue(format!(
+                "Color encoding {:?} is unsupported",
+                color_encoding
+            ))
+        );
+    }
+
+    if let Some(exif) = &enc.exif {
+        add_exif_box(enc_raw, exif)?;
+    }
+
+    for (frame, info) in frames {
+        let options = enc.create_options(enc_raw)?;
+
+        if let Some(info) = info {
+            set_frame_info(options, basic_info, info)?;
+        }
+
+        let (xsize, ysize) = match info {
+            Some(info) if info.have_crop => (info.xsize, info.ysize),
+            _ => (basic_info.xsize, basic_info.ysize),
+        };
+        add_frame(
+            enc_raw,
+            options,
+            &enc.jpeg,
+            *frame,
+            xsize as usize,
+            ysize as usize,
+        )?;
+    }
+
+    unsafe { JxlEncoderCloseInput(enc_raw) };
+
+    Ok(())
+}
 
-    let options = enc.create_options(enc_raw)?;
+fn add_exif_box(enc_raw: *mut JxlEncoderStruct, exif: &[u8]) -> Result<(), JxlEncodeError> {
+    try_enc_fatal!(JxlEncoderUseContainer(enc_raw, true as _));
+    try_enc_fatal!(JxlEncoderUseBoxes(enc_raw));
+
+    // The box starts with the offset to the TIFF header, which directly follows here
+    let mut contents = vec![0u8; 4];
+    contents.extend_from_slice(exif);
+    try_enc!(
+        JxlEncoderAddBox(
+            enc_raw,
+            b"Exif".as_ptr() as *const _,
+            contents.as_ptr(),
+            contents.len(),
+            false as _,
+        ),
+        JxlEncodeError::UnsupportedValue("Exif box is unsupported".to_string())
+    );
+    Ok(())
+}
 
+fn set_frame_info(
+    options: *mut JxlEncoderFrameSettings,
+    basic_info: &JxlBasicInfo,
+    info: &FrameInfo,
+) -> Result<(), JxlEncodeError> {
+    let mut header = JxlFrameHeader::default();
+    unsafe { JxlEncoderInitFrameHeader(&mut header) };
+
+    header.duration = info.duration;
+    header.layer_info.have_crop = info.have_crop as _;
+    header.layer_info.crop_x0 = info.crop_x0;
+    header.layer_info.crop_y0 = info.crop_y0;
+    header.layer_info.xsize = info.xsize;
+    header.layer_info.ysize = info.ysize;
+    header.layer_info.blend_info = info.blend_info;
+    header.layer_info.save_as_reference = info.save_as_reference;
+
+    try_enc!(
+        JxlEncoderSetFrameHeader(options, &header),
+        JxlEncodeError::UnsupportedValue("Frame info is unsupported".to_string())
+    );
+
+    // Blend the extra channels (alpha) the same way as the color channels
+    for index in 0..basic_info.num_extra_channels {
+        try_enc!(
+            JxlEncoderSetExtraChannelBlendInfo(options, index as usize, &info.blend_info),
+            JxlEncodeError::UnsupportedValue(format!(
+                "Blend info is unsupported for extra channel {}",
+                index
+            ))
+        );
+    }
+
+    Ok(())
+}
+
+fn add_frame(
+    enc_raw: *mut JxlEncoderStruct,
+    options: *mut JxlEncoderFrameSettings,
+    jpeg: &JpegOptions,
+    frame: &dyn InputFrame,
+    xsize: usize,
+    ysize: usize,
+) -> Result<(), JxlEncodeError> {
     match frame.get_type() {
         FrameType::Bitmap => {
-            let pixel_format = JxlPixelFormat {
-                num_channels: 4,
-                data_type: JXL_TYPE_UINT8,
-                endianness: JXL_NATIVE_ENDIAN,
-                align: 0,
+            let pixel_format = frame.get_pixel_format();
+            let data = frame.get_data();
+
+            let num_channels = pixel_format.num_channels as usize;
+            if !(1..=4).contains(&num_channels) {
+                return Err(JxlEncodeError::UnsupportedValue(format!(
+                    "Channel count {} is unsupported",
+                    num_channels
+                )));
+            }
+            let sample_size = bytes_per_sample(pixel_format.data_type).ok_or_else(|| {
+                JxlEncodeError::UnsupportedValue(format!(
+                    "Data type {} is unsupported",
+                    pixel_format.data_type
+                ))
+            })?;
+
+            let too_large = || {
+                JxlEncodeError::UnsupportedValue(format!(
+                    "Image size {}x{} is too large",
+                    xsize, ysize
+                ))
             };
-            try_enc_fatal!(JxlEncoderAddImageFrame(
-                options,
-                &pixel_format,
-                frame.get_data().as_ptr() as *mut std::ffi::c_void,
-                frame.get_data().len(),
-            ));
+            if frame.is_planar() {
+                let row_size = xsize.checked_mul(sample_size).ok_or_else(too_large)?;
+                let stride = row_stride(row_size, pixel_format.align).ok_or_else(too_large)?;
+                let size = stride
+                    .checked_mul(ysize)
+                    .and_then(|plane_size| plane_size.checked_mul(num_channels - 1))
+                    .and_then(|planes_size| {
+                        planes_size.checked_add(buffer_size(row_size, stride, ysize)?)
+                    })
+                    .ok_or_else(too_large)?;
+                if data.len() < size {
+                    return Err(JxlEncodeError::BufferTooSmall);
+                }
+
+                // libjxl only takes interleaved pixels
+                let interleaved = interleave(data, xsize, ysize, num_channels, sample_size, stride)
+                    .ok_or_else(too_large)?;
+                let pixel_format = JxlPixelFormat {
+                    align: 0,
+                    ..pixel_format
+                };
+                add_image_frame(options, &pixel_format, &interleaved)
+            } else {
+                let row_size = xsize
+                    .checked_mul(num_channels * sample_size)
+                    .ok_or_else(too_large)?;
+                let stride = row_stride(row_size, pixel_format.align).ok_or_else(too_large)?;
+                let size = buffer_size(row_size, stride, ysize).ok_or_else(too_large)?;
+                if data.len() < size {
+                    return Err(JxlEncodeError::BufferTooSmall);
+                }
+
+                add_image_frame(options, &pixel_format, data)
+            }
         }
         FrameType::Jpeg => {
-            try_enc_fatal!(JxlEncoderStoreJPEGMetadata(enc_raw, 1));
-            try_enc_fatal!(JxlEncoderAddJPEGFrame(
-                options,
-                frame.get_data().as_ptr(),
-                frame.get_data().len(),
-            ))
+            if let Some(effort) = jpeg.effort {
+                try_enc!(
+                    JxlEncoderOptionsSetEffort(options, effort as c_int),
+                    JxlEncodeError::UnsupportedValue(format!(
+                        "Effort value {} is unsupported",
+                        effort
+                    ))
+                );
+            }
+            let store_reconstruction = jpeg.store_reconstruction.unwrap_or(true);
+            try_enc_fatal!(JxlEncoderStoreJPEGMetadata(
+                enc_raw,
+                store_reconstruction as _
+            ));
+            try_enc!(
+                JxlEncoderAddJPEGFrame(options, frame.get_data().as_ptr(), frame.get_data().len()),
+                JxlEncodeError::UnsupportedValue(
+                    "JPEG frame is invalid or unsupported".to_string()
+                )
+            );
+            Ok(())
         }
     }
+}
 
-    unsafe { JxlEncoderCloseInput(enc_raw) };
-
+fn add_image_frame(
+    options: *mut JxlEncoderFrameSettings,
+    pixel_format: &JxlPixelFormat,
+    data: &[u8],
+) -> Result<(), JxlEncodeError> {
+    try_enc!(
+        JxlEncoderAddImageFrame(
+            options,
+            pixel_format,
+            data.as_ptr() as *mut std::ffi::c_void,
+            data.len(),
+        ),
+        JxlEncodeError::UnsupportedValue(format!(
+            "Pixel format {:?} is unsupported for the basic info",
+            pixel_format
+        ))
+    );
     Ok(())
 }
 
+struct EncodeRaw {
+    encoder: *mut JxlEncoderStruct,
+    parallel_runner: *mut c_void,
+}
+
+impl EncodeRaw {
+    fn new() -> Result<Self, JxlEncodeError> {
+        check_encoder_version().map_err(JxlEncodeError::UnsupportedVersion)?;
+        unsafe {
+            Ok(EncodeRaw {
+                encoder: JxlEncoderCreate(std::ptr::null()),
+                parallel_runner: JxlThreadParallelRunnerCreate(
+                    std::ptr::null(),
+                    JxlThreadParallelRunnerDefaultNumWorkerThreads(),
+                ),
+            })
+        }
+    }
+
+    /** Encodes with a freshly reset encoder, so that it can be called repeatedly */
+    unsafe fn encode(
+        &self,
+        frames: &[(&dyn InputFrame, Option<&FrameInfo>)],
+        enc: &Encoder,
+    ) -> Result<Vec<u8>, JxlEncodeError> {
+        JxlEncoderReset(self.encoder);
+        prepare_encoder(
+            enc,
+            self.encoder,
+            &enc.basic_info,
+            self.parallel_runner,
+            frames,
+        )?;
+        Ok(encode_loop(self.encoder))
+    }
+}
+
+impl Drop for EncodeRaw {
+    fn drop(&mut self) {
+        unsafe {
+            JxlThreadParallelRunnerDestroy(self.parallel_runner);
+            JxlEncoderDestroy(self.encoder);
+        }
+    }
+}
+
 pub unsafe fn encode_oneshot(
+    frames: &[(&dyn InputFrame, Option<&FrameInfo>)],
+    enc: &Encoder,
+) -> Result<Vec<u8>, JxlEncodeError> {
+    let has_jpeg = frames
+        .iter()
+        .any(|(frame, _)| matches!(frame.get_type(), FrameType::Jpeg));
+    if !has_jpeg || !(enc.jpeg.lossy || enc.jpeg.verify) {
+        return EncodeRaw::new()?.encode(frames, enc);
+    }
+
+    match frames {
+        [(frame, info)] => encode_jpeg(*frame, *info, enc),
+        _ => Err(JxlEncodeError::UnsupportedValue(
+            "Lossy recompression and verification need a single JPEG frame".to_string(),
+        )),
+    }
+}
+
+unsafe fn encode_jpeg(
     frame: &dyn InputFrame,
+    info: Option<&FrameInfo>,
     enc: &Encoder,
 ) -> Result<Vec<u8>, JxlEncodeError> {
-    let runner = JxlThreadParallelRunnerCreate(
-        std::ptr::null(),
-        JxlThreadParallelRunnerDefaultNumWorkerThreads(),
-    );
+    let raw = EncodeRaw::new()?;
+    if !enc.jpeg.lossy {
+        if enc.jpeg.store_reconstruction == Some(false) {
+            return Err(JxlEncodeError::UnsupportedValue(
+                "Verification needs the JPEG reconstruction data".to_string(),
+            ));
+        }
 
-    let enc_raw = JxlEncoderCreate(std::ptr::null());
+        let encoded = raw.encode(&[(frame, info)], enc)?;
+        return match reconstruct_jpeg(&encoded) {
+            Ok(Some(reconstructed)) if reconstructed == frame.get_data() => Ok(encoded),
+            _ => Err(JxlEncodeError::VerificationFailed),
+        };
+    }
+    if enc.jpeg.verify {
+        return Err(JxlEncodeError::UnsupportedValue(
+            "Lossy recompression cannot be verified".to_string(),
+        ));
+    }
 
-    prepare_encoder(enc, enc_raw, &enc.basic_info, runner, frame)?;
+    // Transcode losslessly first, so that libjxl decodes the DCT coefficients as they are
+    let transcoder = Encoder {
+        lossless: None,
+        distance: None,
+        quality: None,
+        jpeg: JpegOptions {
+            store_reconstruction: Some(false),
+            effort: enc.jpeg.effort,
+            ..Default::default()
+        },
+        ..enc.clone()
+    };
+    let transcoded = raw.encode(&[(frame, None)], &transcoder)?;
+
+    let basic_info = &enc.basic_info;
+    let num_channels = basic_info.num_color_channels;
+    let size = (basic_info.xsize as usize)
+        .checked_mul(basic_info.ysize as usize)
+        .and_then(|pixels| pixels.checked_mul(num_channels as usize))
+        .ok_or_else(|| {
+            JxlEncodeError::UnsupportedValue(format!(
+                "Image size {}x{} is too large",
+                basic_info.xsize, basic_info.ysize
+            ))
+        })?;
+    let mut pixels = vec![0u8; size];
+    let decoder = Decoder {
+        need_color_profile: true,
+        ..Default::default()
+    };
+    let decoded = decoder
+        .decode_into(&transcoded, &mut pixels, num_channels, 0)
+        .map_err(|_| {
+            JxlEncodeError::UnsupportedValue("Basic info does not match the JPEG frame".to_string())
+        })?;
+
+    // The pixels are in the color space of the JPEG, so keep its profile instead of sRGB
+    let mut recompressor = enc.clone();
+    recompressor.basic_info.uses_original_profile = false as _;
+    if !decoded.color_profile.is_empty() {
+        recompressor.color_profile = Some(decoded.color_profile);
+    }
+    let pixel_frame = PixelFrame {
+        data: &pixels,
+        pixel_format: JxlPixelFormat {
+            num_channels,
+            data_type: JXL_TYPE_UINT8,
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 0,
+        },
+        planar: false,
+    };
+    raw.encode(&[(&pixel_frame, info)], &recompressor)
+}
+
+const MIN_SEARCH_DISTANCE: f32 = 0.1;
+const MAX_SEARCH_DISTANCE: f32 = 25.0;
+const MAX_SEARCH_ITERATIONS: usize = 16;
+
+pub unsafe fn encode_to_size(
+    frames: &[(&dyn InputFrame, Option<&FrameInfo>)],
+    enc: &Encoder,
+    target_size: usize,
+    tolerance: f32,
+) -> Result<SizeSearchResult, JxlEncodeError> {
+    if enc.lossless == Some(true) || enc.distance.is_some() || enc.quality.is_some() {
+        return Err(JxlEncodeError::UnsupportedValue(
+            "Target size cannot be used together with lossless, distance or quality".to_string(),
+        ));
+    }
+    // NaN fails the comparison too
+    if !(tolerance > 0.0 && tolerance < 1.0) {
+        return Err(JxlEncodeError::UnsupportedValue(format!(
+            "Tolerance value {} is unsupported",
+            tolerance
+        )));
+    }
+
+    let raw = EncodeRaw::new()?;
+    let mut enc = enc.clone();
+    let mut encode_at = |distance: f32| -> Result<SizeSearchResult, JxlEncodeError> {
+        enc.distance = Some(distance);
+        let data = raw.encode(frames, &enc)?;
+        Ok(SizeSearchResult { data, distance })
+    };
+
+    // The size shrinks as the distance grows, so bisect between the extremes
+    let mut best = encode_at(MAX_SEARCH_DISTANCE)?;
+    if best.data.len() > target_size {
+        return Err(JxlEncodeError::UnsupportedValue(format!(
+            "Target size {} is smaller than the minimum size {}",
+            target_size,
+            best.data.len()
+        )));
+    }
+
+    let lowest = encode_at(MIN_SEARCH_DISTANCE)?;
+    if lowest.data.len() <= target_size {
+        return Ok(lowest);
+    }
 
-    let result = encode_loop(enc_raw);
+    let min_size = target_size as f32 * (1.0 - tolerance);
+    let (mut low, mut high) = (MIN_SEARCH_DISTANCE, MAX_SEARCH_DISTANCE);
+    for _ in 0..MAX_SEARCH_ITERATIONS {
+        // Bisect geometrically as the size changes roughly logarithmically with the distance
+        let result = encode_at((low * high).sqrt())?;
+        if result.data.len() > target_size {
+            low = result.distance;
+            continue;
+        }
 
-    JxlThreadParallelRunnerDestroy(runner);
-    JxlEncoderDestroy(enc_raw);
+        high = result.distance;
+        let is_close_enough = result.data.len() as f32 >= min_size;
+        best = result;
+        if is_close_enough {
+            break;
+        }
+    }
 
-    Ok(result)
+    Ok(best)
 }
 
 pub enum FrameType {
@@ -128,6 +515,21 @@ pub enum FrameType {
 pub trait InputFrame<'a> {
     fn get_type(&self) -> FrameType;
     fn get_data(&self) -> &'a [u8];
+
+    /** The layout of `get_data()` for `FrameType::Bitmap`, which is 8-bit RGBA by default */
+    fn get_pixel_format(&self) -> PixelFormat {
+        JxlPixelFormat {
+            num_channels: 4,
+            data_type: JXL_TYPE_UINT8,
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 0,
+        }
+    }
+
+    /** Whether `get_data()` stores each channel in its own plane */
+    fn is_planar(&self) -> bool {
+        false
+    }
 }
 
 pub struct BitmapFrame<'a> {
@@ -144,6 +546,39 @@ impl<'a> InputFrame<'a> for BitmapFrame<'a> {
     }
 }
 
+/** A bitmap frame in an arbitrary pixel format, as opposed to 8-bit RGBA of `BitmapFrame` */
+pub struct PixelFrame<'a> {
+    pub data: &'a [u8],
+    /**
+     * The channel count, sample type, endianness and row alignment of `data`.
+     * `Encoder::basic_info` should match it, e.g. `bits_per_sample` and `num_color_channels`.
+     */
+    pub pixel_format: PixelFormat,
+    /**
+     * Stores each channel as a separate plane one after another when set to true,
+     * where the rows of each plane are padded to a multiple of `pixel_format.align` bytes.
+     */
+    pub planar: bool,
+}
+
+impl<'a> InputFrame<'a> for PixelFrame<'a> {
+    fn get_type(&self) -> FrameType {
+        FrameType::Bitmap
+    }
+
+    fn get_data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn get_pixel_format(&self) -> PixelFormat {
+        self.pixel_format
+    }
+
+    fn is_planar(&self) -> bool {
+        self.planar
+    }
+}
+
 pub struct JpegFrame<'a> {
     pub data: &'a [u8],
 }
@@ -158,10 +593,97 @@ impl<'a> InputFrame<'a> for JpegFrame<'a> {
     }
 }
 
+/** Per-frame header for layered images and animations */
+#[derive(Default, Clone)]
+pub struct FrameInfo {
+    /** Duration in ticks of `basic_info.animation`, where 0 makes it a layer of the next frame */
+    pub duration: u32,
+    /** Makes the frame cover only part of the canvas when set to true */
+    pub have_crop: bool,
+    /** Horizontal offset of the frame on the canvas, which can be negative */
+    pub crop_x0: i32,
+    /** Vertical offset of the frame on the canvas, which can be negative */
+    pub crop_y0: i32,
+    /** Width of the frame data, ignored unless `have_crop` is set */
+    pub xsize: u32,
+    /** Height of the frame data, ignored unless `have_crop` is set */
+    pub ysize: u32,
+    /** How the frame is blended onto the canvas, using `JXL_BLEND_*` as `blendmode` */
+    pub blend_info: BlendInfo,
+    /** The reference slot (0-3) to save this frame in for later frames to blend against */
+    pub save_as_reference: u32,
+}
+
+/**
+ * Maps a libjpeg-style quality from 0 to 100 to a butteraugli distance, using the same curve as
+ * cjxl. Quality 90 maps to distance 1.0 and quality 100 maps to 0.0 (mathematically lossless).
+ */
+pub fn distance_from_quality(quality: f32) -> f32 {
+    if quality >= 100.0 {
+        0.0
+    } else if quality >= 30.0 {
+        0.1 + (100.0 - quality) * 0.09
+    } else {
+        53.0 / 3000.0 * quality * quality - 23.0 / 20.0 * quality + 25.0
+    }
+}
+
+/** The budget for `Encoder::encode_to_size` */
+#[derive(Debug, Clone, Copy)]
+pub enum TargetSize {
+    Bytes(usize),
+    BitsPerPixel(f32),
+}
+
+/** The output of `Encoder::encode_to_size` */
+#[derive(Debug)]
+pub struct SizeSearchResult {
+    /** The encoded image, which is never larger than the target size */
+    pub data: Vec<u8>,
+    /** The distance used to encode `data` */
+    pub distance: f32,
+}
+
+/** How `JpegFrame`s are encoded */
+#[derive(Debug, Default, Clone)]
+pub struct JpegOptions {
+    /** Stores the data to reconstruct the original JPEG file, which is true by default */
+    pub store_reconstruction: Option<bool>,
+    /** Effort for JPEG frames, overriding `Encoder::effort` */
+    pub effort: Option<i32>,
+    /**
+     * Recompresses lossily at `Encoder::distance` or `Encoder::quality` from the pixels decoded
+     * from the DCT coefficients, instead of keeping the coefficients as they are.
+     * The ICC profile of the JPEG file is kept, as the pixels are decoded in its color space.
+     */
+    pub lossy: bool,
+    /**
+     * Reconstructs the JPEG file from the output and returns `JxlEncodeError::VerificationFailed`
+     * unless it is identical to the input. Cannot be used together with `lossy`.
+     */
+    pub verify: bool,
+}
+
+#[derive(Clone)]
 pub struct Encoder {
     pub lossless: Option<bool>,
     pub effort: Option<i32>,
     pub distance: Option<f32>,
+    /**
+     * Quality from 0 to 100 that is mapped to `distance` by `distance_from_quality`, where 100
+     * means lossless. Cannot be used together with `lossless` or `distance`.
+     */
+    pub quality: Option<f32>,
+    /** Fine-grained settings applied to every frame */
+    pub frame_settings: FrameSettings,
+    /** Options for `JpegFrame`s */
+    pub jpeg: JpegOptions,
+    /** ICC profile of the input pixels, taking precedence over `color_encoding` */
+    pub color_profile: Option<Vec<u8>>,
+    /** Color encoding of the input pixels, which is sRGB by default */
+    pub color_encoding: Option<JxlColorEncoding>,
+    /** Exif metadata starting with the TIFF header, stored in an `Exif` box */
+    pub exif: Option<Vec<u8>>,
     pub basic_info: JxlBasicInfo,
 }
 
@@ -177,6 +699,9 @@ impl Encoder {
     ) -> Result<*mut JxlEncoderFrameSettings, JxlEncodeError> {
         let options = unsafe { JxlEncoderOptionsCreate(enc_raw, std::ptr::null()) };
 
+        if let Some(quality) = self.quality {
+            self.set_quality(options, quality)?;
+        }
         if let Some(lossless) = self.lossless {
             try_enc_fatal!(JxlEncoderOptionsSetLossless(options, lossless as i32));
         }
@@ -195,17 +720,104 @@ impl Encoder {
                 ))
             );
         }
+        for (id, value) in self.frame_settings.to_float_options() {
+            try_enc!(
+                JxlEncoderFrameSettingsSetFloatOption(options, id, value),
+                JxlEncodeError::UnsupportedValue(format!(
+                    "Value {} is unsupported for frame setting {}",
+                    value, id
+                ))
+            );
+        }
+        for (id, value) in self.frame_settings.to_options() {
+            try_enc!(
+                JxlEncoderFrameSettingsSetOption(options, id, value),
+                JxlEncodeError::UnsupportedValue(format!(
+                    "Value {} is unsupported for frame setting {}",
+                    value, id
+                ))
+            );
+        }
 
         Ok(options)
     }
 
+    fn set_quality(
+        &self,
+        options: *mut JxlEncoderFrameSettings,
+        quality: f32,
+    ) -> Result<(), JxlEncodeError> {
+        if self.lossless.is_some() || self.distance.is_some() {
+            return Err(JxlEncodeError::UnsupportedValue(
+                "Quality cannot be used together with lossless or distance".to_string(),
+            ));
+        }
+        if !(0.0..=100.0).contains(&quality) {
+            return Err(JxlEncodeError::UnsupportedValue(format!(
+                "Quality value {} is unsupported",
+                quality
+            )));
+        }
+
+        let distance = distance_from_quality(quality);
+        if distance == 0.0 {
+            try_enc_fatal!(JxlEncoderOptionsSetLossless(options, 1));
+        } else {
+            try_enc_fatal!(JxlEncoderOptionsSetDistance(options, distance));
+        }
+        Ok(())
+    }
+
     pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>, JxlEncodeError> {
         let frame = BitmapFrame { data };
         self.encode_frame(&frame)
     }
 
     pub fn encode_frame(&self, frame: &dyn InputFrame) -> Result<Vec<u8>, JxlEncodeError> {
-        unsafe { encode_oneshot(frame, self) }
+        unsafe { encode_oneshot(&[(frame, None)], self) }
+    }
+
+    /** Encodes multiple frames as layers or animation frames, as specified by each `FrameInfo` */
+    pub fn encode_frames(
+        &self,
+        frames: &[(&dyn InputFrame, FrameInfo)],
+    ) -> Result<Vec<u8>, JxlEncodeError> {
+        let frames: Vec<_> = frames
+            .iter()
+            .map(|(frame, info)| (*frame, Some(info)))
+            .collect();
+        unsafe { encode_oneshot(&frames, self) }
+    }
+
+    /**
+     * Searches the distance that makes the output fit in `target`, by encoding repeatedly until
+     * the size is within `tolerance` (e.g. 0.05 for 5%) below the target, which must be greater
+     * than 0 and less than 1. Cannot be used together with `lossless`, `distance` or `quality`.
+     */
+    pub fn encode_to_size(
+        &self,
+        data: &[u8],
+        target: TargetSize,
+        tolerance: f32,
+    ) -> Result<SizeSearchResult, JxlEncodeError> {
+        let frame = BitmapFrame { data };
+        self.encode_frame_to_size(&frame, target, tolerance)
+    }
+
+    pub fn encode_frame_to_size(
+        &self,
+        frame: &dyn InputFrame,
+        target: TargetSize,
+        tolerance: f32,
+    ) -> Result<SizeSearchResult, JxlEncodeError> {
+        let target_size = match target {
+            TargetSize::Bytes(bytes) => bytes,
+            TargetSize::BitsPerPixel(bpp) => {
+                let pixels = self.basic_info.xsize as f32 * self.basic_info.ysize as f32;
+                (bpp * pixels / 8.0) as usize
+            }
+        };
+        unsafe { encode_to_size(&[(frame, None)], self, target_size, tolerance) }
     }
 }
 
@@ -223,6 +835,12 @@ impl Default for Encoder {
             lossless: None,
             effort: None,
             distance: None,
+            quality: None,
+            frame_settings: FrameSettings::default(),
+            jpeg: JpegOptions::default(),
+            color_profile: None,
+            color_encoding: None,
+            exif: None,
             basic_info,
         }
     }
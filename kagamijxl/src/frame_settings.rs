@@ -0,0 +1,246 @@
+use libjxl_sys::*;
+
+/**
+ * Encoder settings mapped to `JxlEncoderFrameSettingId`, where `None` keeps the libjxl default.
+ * See the libjxl documentation of each `JXL_ENC_FRAME_SETTING_*` for the valid values.
+ */
+#[derive(Debug, Default, Clone)]
+pub struct FrameSettings {
+    /** Decoding speed tier from 0 (slowest to decode, best quality) to 4 */
+    pub decoding_speed: Option<i64>,
+    /** Downsampling factor of the color channels: 1, 2, 4 or 8 */
+    pub resampling: Option<i64>,
+    /** Downsampling factor of the extra channels: 1, 2, 4 or 8 */
+    pub extra_channel_resampling: Option<i64>,
+    /** Tells that the input is already downsampled by `resampling` */
+    pub already_downsampled: Option<bool>,
+    /** Adds noise to the decoded image as if taken with the given ISO speed */
+    pub photon_noise: Option<f32>,
+    /** Enables adaptive noise generation */
+    pub noise: Option<bool>,
+    /** Enables dots generation */
+    pub dots: Option<bool>,
+    /** Enables patches generation */
+    pub patches: Option<bool>,
+    /** Edge preserving filter level from 0 to 3 */
+    pub epf: Option<i64>,
+    /** Enables the gaborish filter */
+    pub gaborish: Option<bool>,
+    /** Uses modular mode when set to true, or VarDCT mode when set to false */
+    pub modular: Option<bool>,
+    /** Keeps the color of invisible pixels */
+    pub keep_invisible: Option<bool>,
+    /** Group order, 0 for scanline order or 1 for center-first order */
+    pub group_order: Option<i64>,
+    /** Horizontal center of center-first group order */
+    pub group_order_center_x: Option<i64>,
+    /** Vertical center of center-first group order */
+    pub group_order_center_y: Option<i64>,
+    /** Uses progressive mode for modular mode */
+    pub responsive: Option<bool>,
+    /** Enables spectral progression for VarDCT mode */
+    pub progressive_ac: Option<bool>,
+    /** Enables quantization-based progression for VarDCT mode */
+    pub qprogressive_ac: Option<bool>,
+    /** Progressive DC level from 0 to 2 */
+    pub progressive_dc: Option<i64>,
+    /** Percentage of pixels used for the global channel palette */
+    pub channel_colors_global_percent: Option<f32>,
+    /** Percentage of pixels used for the channel palette of each group */
+    pub channel_colors_group_percent: Option<f32>,
+    /** Maximum number of palette colors */
+    pub palette_colors: Option<i64>,
+    /** Enables delta palette */
+    pub lossy_palette: Option<bool>,
+    /** Color transform, 0 for XYB, 1 for none, or 2 for YCbCr */
+    pub color_transform: Option<i64>,
+    /** Reversible color transform for modular mode */
+    pub modular_color_space: Option<i64>,
+    /** Group size for modular mode, 0 to 3 for 128 to 1024 pixels */
+    pub modular_group_size: Option<i64>,
+    /** Predictor for modular mode from 0 to 15 */
+    pub modular_predictor: Option<i64>,
+    /** Percentage of pixels used to learn the MA tree for modular mode */
+    pub modular_ma_tree_learning_percent: Option<f32>,
+    /** Number of previous channels used for the MA tree context for modular mode */
+    pub modular_nb_prev_channels: Option<i64>,
+    /** Enables chroma from luma for JPEG recompression */
+    pub jpeg_recon_cfl: Option<bool>,
+    /** Prepares a frame index box */
+    pub index_box: Option<bool>,
+
+    /** Raw settings applied after all the others, for settings not covered above */
+    pub raw: Vec<(JxlEncoderFrameSettingId, i64)>,
+    /** Raw float settings such as `JXL_ENC_FRAME_SETTING_PHOTON_NOISE`, applied like `raw` */
+    pub raw_float: Vec<(JxlEncoderFrameSettingId, f32)>,
+}
+
+impl FrameSettings {
+    /** Lists the specified float settings, which libjxl only accepts through the float setter */
+    pub(crate) fn to_float_options(&self) -> Vec<(JxlEncoderFrameSettingId, f32)> {
+        let settings = [
+            (JXL_ENC_FRAME_SETTING_PHOTON_NOISE, self.photon_noise),
+            (
+                JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GLOBAL_PERCENT,
+                self.channel_colors_global_percent,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GROUP_PERCENT,
+                self.channel_colors_group_percent,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_MODULAR_MA_TREE_LEARNING_PERCENT,
+                self.modular_ma_tree_learning_percent,
+            ),
+        ];
+
+        settings
+            .iter()
+            .filter_map(|(id, value)| value.map(|value| (*id, value)))
+            .chain(self.raw_float.iter().copied())
+            .collect()
+    }
+
+    /** Lists the specified integer settings as pairs of the setting ID and its value */
+    pub(crate) fn to_options(&self) -> Vec<(JxlEncoderFrameSettingId, i64)> {
+        let flag = |value: Option<bool>| value.map(|value| value as i64);
+        let settings = [
+            (JXL_ENC_FRAME_SETTING_DECODING_SPEED, self.decoding_speed),
+            (JXL_ENC_FRAME_SETTING_RESAMPLING, self.resampling),
+            (
+                JXL_ENC_FRAME_SETTING_EXTRA_CHANNEL_RESAMPLING,
+                self.extra_channel_resampling,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_ALREADY_DOWNSAMPLED,
+                flag(self.already_downsampled),
+            ),
+            (JXL_ENC_FRAME_SETTING_NOISE, flag(self.noise)),
+            (JXL_ENC_FRAME_SETTING_DOTS, flag(self.dots)),
+            (JXL_ENC_FRAME_SETTING_PATCHES, flag(self.patches)),
+            (JXL_ENC_FRAME_SETTING_EPF, self.epf),
+            (JXL_ENC_FRAME_SETTING_GABORISH, flag(self.gaborish)),
+            (JXL_ENC_FRAME_SETTING_MODULAR, flag(self.modular)),
+            (
+                JXL_ENC_FRAME_SETTING_KEEP_INVISIBLE,
+                flag(self.keep_invisible),
+            ),
+            (JXL_ENC_FRAME_SETTING_GROUP_ORDER, self.group_order),
+            (
+                JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_X,
+                self.group_order_center_x,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_Y,
+                self.group_order_center_y,
+            ),
+            (JXL_ENC_FRAME_SETTING_RESPONSIVE, flag(self.responsive)),
+            (
+                JXL_ENC_FRAME_SETTING_PROGRESSIVE_AC,
+                flag(self.progressive_ac),
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_QPROGRESSIVE_AC,
+                flag(self.qprogressive_ac),
+            ),
+            (JXL_ENC_FRAME_SETTING_PROGRESSIVE_DC, self.progressive_dc),
+            (JXL_ENC_FRAME_SETTING_PALETTE_COLORS, self.palette_colors),
+            (
+                JXL_ENC_FRAME_SETTING_LOSSY_PALETTE,
+                flag(self.lossy_palette),
+            ),
+            (JXL_ENC_FRAME_SETTING_COLOR_TRANSFORM, self.color_transform),
+            (
+                JXL_ENC_FRAME_SETTING_MODULAR_COLOR_SPACE,
+                self.modular_color_space,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_MODULAR_GROUP_SIZE,
+                self.modular_group_size,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_MODULAR_PREDICTOR,
+                self.modular_predictor,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_MODULAR_NB_PREV_CHANNELS,
+                self.modular_nb_prev_channels,
+            ),
+            (
+                JXL_ENC_FRAME_SETTING_JPEG_RECON_CFL,
+                flag(self.jpeg_recon_cfl),
+            ),
+            (JXL_ENC_FRAME_INDEX_BOX, flag(self.index_box)),
+        ];
+
+        settings
+            .iter()
+            .filter_map(|(id, value)| value.map(|value| (*id, value)))
+            .chain(self.raw.iter().copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameSettings;
+    use libjxl_sys::*;
+
+    #[test]
+    fn unspecified() {
+        assert_eq!(FrameSettings::default().to_options(), []);
+    }
+
+    #[test]
+    fn typed_then_raw() {
+        let settings = FrameSettings {
+            epf: Some(2),
+            modular: Some(false),
+            raw: vec![(JXL_ENC_FRAME_SETTING_EPF, 1)],
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.to_options(),
+            [
+                (JXL_ENC_FRAME_SETTING_EPF, 2),
+                (JXL_ENC_FRAME_SETTING_MODULAR, 0),
+                (JXL_ENC_FRAME_SETTING_EPF, 1),
+            ]
+        );
+        assert_eq!(settings.to_float_options(), []);
+    }
+
+    #[test]
+    fn float_settings() {
+        let settings = FrameSettings {
+            photon_noise: Some(3200.0),
+            modular_ma_tree_learning_percent: Some(50.0),
+            ..Default::default()
+        };
+        assert_eq!(settings.to_options(), []);
+        assert_eq!(
+            settings.to_float_options(),
+            [
+                (JXL_ENC_FRAME_SETTING_PHOTON_NOISE, 3200.0),
+                (JXL_ENC_FRAME_SETTING_MODULAR_MA_TREE_LEARNING_PERCENT, 50.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn float_typed_then_raw() {
+        let settings = FrameSettings {
+            photon_noise: Some(3200.0),
+            raw_float: vec![(JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GROUP_PERCENT, 25.0)],
+            ..Default::default()
+        };
+        assert_eq!(settings.to_options(), []);
+        assert_eq!(
+            settings.to_float_options(),
+            [
+                (JXL_ENC_FRAME_SETTING_PHOTON_NOISE, 3200.0),
+                (JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GROUP_PERCENT, 25.0),
+            ]
+        );
+    }
+}
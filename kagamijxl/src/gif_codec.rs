@@ -0,0 +1,404 @@
+use std::io::Read;
+
+use gif::{ColorOutput, DecodeOptions, DisposalMethod, Repeat};
+
+use crate::{
+    encode::InputFrame, BlendInfo, Encoder, FrameInfo, JxlEncodeError, PixelFormat, PixelFrame,
+    Rect,
+};
+use libjxl_sys::*;
+
+/** The reference slot holding the canvas before each frame, as slot 0 is not kept across frames */
+const CANVAS_SLOT: u32 = 1;
+/** The delay in centiseconds that browsers use for frames with a delay of 0 or 1 */
+const MIN_DELAY: u16 = 10;
+
+#[derive(Debug)]
+pub enum GifError {
+    Decoding(gif::DecodingError),
+    Jxl(JxlEncodeError),
+    Unsupported(String),
+}
+
+impl From<gif::DecodingError> for GifError {
+    fn from(err: gif::DecodingError) -> Self {
+        GifError::Decoding(err)
+    }
+}
+
+impl From<JxlEncodeError> for GifError {
+    fn from(err: JxlEncodeError) -> Self {
+        GifError::Jxl(err)
+    }
+}
+
+fn union(a: Rect, b: Rect) -> Rect {
+    let x0 = a.x0.min(b.x0);
+    let y0 = a.y0.min(b.y0);
+    let x1 = (a.x0 + a.xsize).max(b.x0 + b.xsize);
+    let y1 = (a.y0 + a.ysize).max(b.y0 + b.ysize);
+    Rect {
+        x0,
+        y0,
+        xsize: x1 - x0,
+        ysize: y1 - y0,
+    }
+}
+
+/** A cropped frame with the displayed pixels of its region */
+struct CanvasUpdate {
+    data: Vec<u8>,
+    region: Rect,
+    delay: u16,
+}
+
+/**
+ * Tracks the displayed GIF canvas in RGBA, so that each frame can be stored as the region that
+ * changed since the previous one, including the region disposed by the previous frame.
+ */
+struct Canvas {
+    data: Vec<u8>,
+    xsize: u32,
+    ysize: u32,
+    /** The region that differs from the last displayed frame, which is all of it at first */
+    dirty: Option<Rect>,
+}
+
+impl Canvas {
+    fn new(xsize: u32, ysize: u32) -> Self {
+        Self {
+            data: vec![0u8; xsize as usize * ysize as usize * 4],
+            xsize,
+            ysize,
+            dirty: Some(Rect {
+                x0: 0,
+                y0: 0,
+                xsize,
+                ysize,
+            }),
+        }
+    }
+
+    /** The frame region within the canvas, as GIF frames may extend past it */
+    fn clip(&self, frame: &gif::Frame) -> Rect {
+        let x0 = (frame.left as u32).min(self.xsize);
+        let y0 = (frame.top as u32).min(self.ysize);
+        Rect {
+            x0,
+            y0,
+            xsize: (frame.width as u32).min(self.xsize - x0),
+            ysize: (frame.height as u32).min(self.ysize - y0),
+        }
+    }
+
+    fn rows(&self, region: Rect) -> impl Iterator<Item = std::ops::Range<usize>> {
+        let xsize = self.xsize as usize;
+        let (x0, width) = (region.x0 as usize, region.xsize as usize);
+        (region.y0 as usize..(region.y0 + region.ysize) as usize).map(move |y| {
+            let start = (y * xsize + x0) * 4;
+            start..start + width * 4
+        })
+    }
+
+    fn crop(&self, region: Rect) -> Vec<u8> {
+        let mut data = Vec::with_capacity(region.xsize as usize * region.ysize as usize * 4);
+        for range in self.rows(region) {
+            data.extend_from_slice(&self.data[range]);
+        }
+        data
+    }
+
+    fn clear(&mut self, region: Rect) {
+        let rows: Vec<_> = self.rows(region).collect();
+        for range in rows {
+            self.data[range].iter_mut().for_each(|byte| *byte = 0);
+        }
+    }
+
+    /** Draws an RGBA frame, where transparent pixels keep the canvas as GIF has no partial alpha */
+    fn draw(&mut self, frame: &gif::Frame, region: Rect) {
+        if region.xsize == 0 || region.ysize == 0 {
+            return;
+        }
+        let stride = frame.width as usize * 4;
+        let x_offset = (region.x0 - frame.left as u32) as usize * 4;
+        let y_offset = (region.y0 - frame.top as u32) as usize;
+        let rows: Vec<_> = self.rows(region).collect();
+        for (y, range) in rows.into_iter().enumerate() {
+            let start = (y_offset + y) * stride + x_offset;
+            let source_row = &frame.buffer[start..start + range.len()];
+            let target_row = &mut self.data[range];
+            for (source, target) in source_row
+                .chunks_exact(4)
+                .zip(target_row.chunks_exact_mut(4))
+            {
+                if source[3] != 0 {
+                    target.copy_from_slice(source);
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, frame: &gif::Frame) -> CanvasUpdate {
+        let region = self.clip(frame);
+        let previous = match frame.dispose {
+            DisposalMethod::Previous => Some(self.data.clone()),
+            _ => None,
+        };
+        self.draw(frame, region);
+
+        let changed = match self.dirty {
+            Some(dirty) => union(dirty, region),
+            None => region,
+        };
+        // libjxl rejects empty frames, so an unchanged canvas still gets a pixel
+        let changed = match changed.xsize * changed.ysize {
+            0 => Rect {
+                x0: 0,
+                y0: 0,
+                xsize: 1,
+                ysize: 1,
+            },
+            _ => changed,
+        };
+        let update = CanvasUpdate {
+            data: self.crop(changed),
+            region: changed,
+            delay: match frame.delay {
+                // A duration of 0 would make a layer of the next frame instead
+                0 | 1 => MIN_DELAY,
+                delay => delay,
+            },
+        };
+
+        self.dirty = match frame.dispose {
+            DisposalMethod::Background => {
+                // Browsers restore to transparency rather than the background color
+                self.clear(region);
+                Some(region)
+            }
+            DisposalMethod::Previous => {
+                self.data = previous.unwrap();
+                Some(region)
+            }
+            DisposalMethod::Keep | DisposalMethod::Any => None,
+        };
+        update
+    }
+}
+
+impl Encoder {
+    /**
+     * Encodes a GIF file as an animation whose frames cover only the regions that change,
+     * taking its size and loop count instead of the ones specified in this encoder.
+     * Delays of 0 and 1 centiseconds become 10 as in browsers, so that every frame is shown.
+     * Encodes losslessly unless `lossless`, `distance` or `quality` is specified.
+     */
+    pub fn encode_gif(&self, gif: impl Read) -> Result<Vec<u8>, GifError> {
+        let mut options = DecodeOptions::new();
+        options.set_color_output(ColorOutput::RGBA);
+        let mut decoder = options.read_info(gif)?;
+
+        let mut canvas = Canvas::new(decoder.width() as u32, decoder.height() as u32);
+        let mut updates = Vec::new();
+        while let Some(frame) = decoder.read_next_frame()? {
+            updates.push(canvas.add(frame));
+        }
+        if updates.is_empty() {
+            return Err(GifError::Unsupported("GIF has no frame".to_string()));
+        }
+
+        let mut enc = self.clone();
+        if enc.lossless.is_none() && enc.distance.is_none() && enc.quality.is_none() {
+            enc.lossless = Some(true);
+        }
+        let basic_info = &mut enc.basic_info;
+        basic_info.xsize = canvas.xsize;
+        basic_info.ysize = canvas.ysize;
+        basic_info.bits_per_sample = 8;
+        basic_info.exponent_bits_per_sample = 0;
+        basic_info.num_color_channels = 3;
+        basic_info.num_extra_channels = 1;
+        basic_info.alpha_bits = 8;
+        basic_info.alpha_exponent_bits = 0;
+        basic_info.have_animation = true as _;
+        // GIF delays are in centiseconds
+        basic_info.animation.tps_numerator = 100;
+        basic_info.animation.tps_denominator = 1;
+        // The loop count of GIF is the number of repetitions after the first play
+        basic_info.animation.num_loops = match decoder.repeat() {
+            Repeat::Infinite => 0,
+            Repeat::Finite(count) => count as u32 + 1,
+        };
+
+        let pixel_format = PixelFormat {
+            num_channels: 4,
+            data_type: JXL_TYPE_UINT8,
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 0,
+        };
+        let pixel_frames: Vec<_> = updates
+            .iter()
+            .map(|update| PixelFrame {
+                data: &update.data,
+                pixel_format,
+                planar: false,
+            })
+            .collect();
+
+        let last = updates.len() - 1;
+        let frames: Vec<_> = pixel_frames
+            .iter()
+            .zip(&updates)
+            .enumerate()
+            .map(|(index, (frame, update))| {
+                let blend_info = BlendInfo {
+                    blendmode: JXL_BLEND_REPLACE,
+                    source: CANVAS_SLOT,
+                    ..Default::default()
+                };
+                let info = FrameInfo {
+                    duration: update.delay as u32,
+                    have_crop: true,
+                    crop_x0: update.region.x0 as i32,
+                    crop_y0: update.region.y0 as i32,
+                    xsize: update.region.xsize,
+                    ysize: update.region.ysize,
+                    blend_info,
+                    save_as_reference: if index == last { 0 } else { CANVAS_SLOT },
+                };
+                (frame as &dyn InputFrame, info)
+            })
+            .collect();
+        Ok(enc.encode_frames(&frames)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{union, Canvas};
+    use crate::Rect;
+    use gif::DisposalMethod;
+    use std::borrow::Cow;
+
+    fn frame(
+        rect: (u16, u16, u16, u16),
+        pixel: [u8; 4],
+        dispose: DisposalMethod,
+    ) -> gif::Frame<'static> {
+        let (left, top, width, height) = rect;
+        let buffer = pixel.repeat(width as usize * height as usize);
+        gif::Frame {
+            left,
+            top,
+            width,
+            height,
+            dispose,
+            delay: 5,
+            buffer: Cow::Owned(buffer),
+            ..Default::default()
+        }
+    }
+
+    fn tuple(rect: Rect) -> (u32, u32, u32, u32) {
+        (rect.x0, rect.y0, rect.xsize, rect.ysize)
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn union_regions() {
+        let a = Rect {
+            x0: 1,
+            y0: 2,
+            xsize: 2,
+            ysize: 2,
+        };
+        let b = Rect {
+            x0: 4,
+            y0: 0,
+            xsize: 1,
+            ysize: 1,
+        };
+        assert_eq!(tuple(union(a, b)), (1, 0, 4, 4));
+    }
+
+    #[test]
+    fn first_frame_covers_canvas() {
+        let mut canvas = Canvas::new(3, 2);
+        let update = canvas.add(&frame((1, 1, 1, 1), RED, DisposalMethod::Keep));
+        assert_eq!(tuple(update.region), (0, 0, 3, 2));
+        assert_eq!(update.delay, 5);
+        assert_eq!(&update.data[16..20], RED);
+        assert_eq!(&update.data[0..4], CLEAR);
+    }
+
+    #[test]
+    fn short_delay_clamped() {
+        let mut canvas = Canvas::new(1, 1);
+        for delay in [0, 1] {
+            let update = canvas.add(&gif::Frame {
+                delay,
+                ..frame((0, 0, 1, 1), RED, DisposalMethod::Keep)
+            });
+            assert_eq!(update.delay, 10);
+        }
+        let update = canvas.add(&gif::Frame {
+            delay: 2,
+            ..frame((0, 0, 1, 1), RED, DisposalMethod::Keep)
+        });
+        assert_eq!(update.delay, 2);
+    }
+
+    #[test]
+    fn keep_crops_to_frame() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.add(&frame((0, 0, 3, 2), RED, DisposalMethod::Keep));
+        let update = canvas.add(&frame((2, 1, 1, 1), BLUE, DisposalMethod::Keep));
+        assert_eq!(tuple(update.region), (2, 1, 1, 1));
+        assert_eq!(update.data, BLUE);
+    }
+
+    #[test]
+    fn transparent_keeps_canvas() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.add(&frame((0, 0, 1, 1), RED, DisposalMethod::Keep));
+        let update = canvas.add(&frame((0, 0, 1, 1), CLEAR, DisposalMethod::Keep));
+        assert_eq!(update.data, RED);
+    }
+
+    #[test]
+    fn background_clears_region() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.add(&frame((0, 0, 2, 1), RED, DisposalMethod::Keep));
+        canvas.add(&frame((0, 0, 1, 1), BLUE, DisposalMethod::Background));
+        let update = canvas.add(&frame((1, 0, 1, 1), BLUE, DisposalMethod::Keep));
+        assert_eq!(tuple(update.region), (0, 0, 2, 1));
+        assert_eq!(update.data, [CLEAR, BLUE].concat());
+    }
+
+    #[test]
+    fn previous_restores_canvas() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.add(&frame((0, 0, 2, 1), RED, DisposalMethod::Keep));
+        canvas.add(&frame((0, 0, 1, 1), BLUE, DisposalMethod::Previous));
+        let update = canvas.add(&frame((1, 0, 1, 1), BLUE, DisposalMethod::Keep));
+        assert_eq!(tuple(update.region), (0, 0, 2, 1));
+        assert_eq!(update.data, [RED, BLUE].concat());
+    }
+
+    #[test]
+    fn clips_to_canvas() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.add(&frame((0, 0, 2, 2), RED, DisposalMethod::Keep));
+        let update = canvas.add(&frame((1, 1, 3, 3), BLUE, DisposalMethod::Keep));
+        assert_eq!(tuple(update.region), (1, 1, 1, 1));
+        assert_eq!(update.data, BLUE);
+
+        let update = canvas.add(&frame((5, 5, 1, 1), BLUE, DisposalMethod::Keep));
+        assert_eq!(tuple(update.region), (0, 0, 1, 1));
+        assert_eq!(update.data, RED);
+    }
+}
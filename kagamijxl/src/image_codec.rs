@@ -0,0 +1,274 @@
+#[cfg(feature = "encoder")]
+use std::io::Write;
+use std::{
+    convert::TryFrom,
+    io::{Cursor, Read},
+};
+
+use image::{
+    error::{DecodingError, ImageFormatHint},
+    AnimationDecoder, ColorType, Delay, Frames, ImageDecoder, ImageError, ImageResult, RgbaImage,
+};
+#[cfg(feature = "encoder")]
+use image::{
+    error::{EncodingError, UnsupportedError, UnsupportedErrorKind},
+    ImageEncoder,
+};
+
+use crate::{BasicInfo, Decoder, PixelFormat};
+#[cfg(feature = "encoder")]
+use crate::{Encoder, PixelFrame};
+use libjxl_sys::*;
+
+fn format_hint() -> ImageFormatHint {
+    ImageFormatHint::Name("JPEG XL".to_string())
+}
+
+fn decoding_error(err: impl std::fmt::Debug) -> ImageError {
+    ImageError::Decoding(DecodingError::new(format_hint(), format!("{:?}", err)))
+}
+
+#[cfg(feature = "encoder")]
+fn encoding_error(err: impl std::fmt::Debug) -> ImageError {
+    ImageError::Encoding(EncodingError::new(format_hint(), format!("{:?}", err)))
+}
+
+/** The closest color type that keeps the precision and the channels of the image */
+fn color_type_from(basic_info: &BasicInfo) -> ColorType {
+    let is_gray = basic_info.num_color_channels == 1;
+    let has_alpha = basic_info.alpha_bits > 0;
+
+    // The image crate has no grayscale float types
+    if basic_info.exponent_bits_per_sample > 0 {
+        return match has_alpha {
+            true => ColorType::Rgba32F,
+            false => ColorType::Rgb32F,
+        };
+    }
+
+    match (basic_info.bits_per_sample > 8, is_gray, has_alpha) {
+        (false, true, false) => ColorType::L8,
+        (false, true, true) => ColorType::La8,
+        (false, false, false) => ColorType::Rgb8,
+        (false, false, true) => ColorType::Rgba8,
+        (true, true, false) => ColorType::L16,
+        (true, true, true) => ColorType::La16,
+        (true, false, false) => ColorType::Rgb16,
+        (true, false, true) => ColorType::Rgba16,
+    }
+}
+
+fn pixel_format_from(color_type: ColorType) -> Option<PixelFormat> {
+    let data_type = match color_type {
+        ColorType::L8 | ColorType::La8 | ColorType::Rgb8 | ColorType::Rgba8 => JXL_TYPE_UINT8,
+        ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 => JXL_TYPE_UINT16,
+        ColorType::Rgb32F | ColorType::Rgba32F => JXL_TYPE_FLOAT,
+        _ => return None,
+    };
+    Some(PixelFormat {
+        num_channels: color_type.channel_count() as u32,
+        data_type,
+        endianness: JXL_NATIVE_ENDIAN,
+        align: 0,
+    })
+}
+
+/** Decodes JPEG XL images for the `image` crate */
+pub struct JxlImageDecoder {
+    data: Vec<u8>,
+    basic_info: BasicInfo,
+}
+
+impl JxlImageDecoder {
+    /** Reads the whole image from `reader` and decodes its header */
+    pub fn new(mut reader: impl Read) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let decoder = Decoder {
+            no_full_frame: true,
+            ..Default::default()
+        };
+        let result = decoder.decode(&data).map_err(decoding_error)?;
+
+        Ok(Self {
+            data,
+            basic_info: result.basic_info,
+        })
+    }
+
+    pub fn basic_info(&self) -> &BasicInfo {
+        &self.basic_info
+    }
+}
+
+impl<'a> ImageDecoder<'a> for JxlImageDecoder {
+    type Reader = Cursor<Vec<u8>>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.basic_info.xsize, self.basic_info.ysize)
+    }
+
+    fn color_type(&self) -> ColorType {
+        color_type_from(&self.basic_info)
+    }
+
+    fn into_reader(self) -> ImageResult<Self::Reader> {
+        let mut buffer = vec![0u8; self.total_bytes() as usize];
+        self.read_image(&mut buffer)?;
+        Ok(Cursor::new(buffer))
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        let pixel_format = pixel_format_from(self.color_type())
+            .expect("The color type should always have a pixel format");
+        Decoder::default()
+            .decode_into_format(&self.data, buf, &pixel_format)
+            .map_err(decoding_error)?;
+        Ok(())
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+/** Converts a duration in ticks of tps_denominator / tps_numerator seconds */
+fn frame_delay(duration: u32, animation: &JxlAnimationHeader) -> Delay {
+    if animation.tps_numerator == 0 {
+        return Delay::from_numer_denom_ms(0, 1);
+    }
+
+    // The milliseconds can exceed u32 before the ratio is reduced
+    let numerator = duration as u128 * animation.tps_denominator as u128 * 1000;
+    let denominator = animation.tps_numerator as u128;
+    let divisor = gcd(numerator, denominator);
+    let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+    match u32::try_from(numerator) {
+        Ok(numerator) => Delay::from_numer_denom_ms(numerator, denominator as u32),
+        Err(_) => {
+            let ms = (numerator / denominator).min(u32::MAX as u128);
+            Delay::from_numer_denom_ms(ms as u32, 1)
+        }
+    }
+}
+
+impl<'a> AnimationDecoder<'a> for JxlImageDecoder {
+    /** Decodes the frames as 8-bit RGBA, as the image crate requires */
+    fn into_frames(self) -> Frames<'a> {
+        let result = match Decoder::default().decode(&self.data) {
+            Ok(result) => result,
+            Err(err) => return Frames::new(Box::new(std::iter::once(Err(decoding_error(err))))),
+        };
+
+        let animation = result.basic_info.animation;
+        let frames = result.frames.into_iter().map(move |frame| {
+            let delay = frame_delay(frame.duration, &animation);
+            let buffer = RgbaImage::from_raw(frame.xsize, frame.ysize, frame.data)
+                .ok_or_else(|| decoding_error("Frame size does not match its data"))?;
+            Ok(image::Frame::from_parts(buffer, 0, 0, delay))
+        });
+        Frames::new(Box::new(frames))
+    }
+}
+
+/** Encodes JPEG XL images for the `image` crate */
+#[cfg(feature = "encoder")]
+pub struct JxlImageEncoder<W: Write> {
+    writer: W,
+    encoder: Encoder,
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> JxlImageEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_encoder(writer, Encoder::default())
+    }
+
+    /** Encodes with the options of `encoder`, whose basic info is replaced to match the image */
+    pub fn with_encoder(writer: W, encoder: Encoder) -> Self {
+        Self { writer, encoder }
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> ImageEncoder for JxlImageEncoder<W> {
+    fn write_image(
+        mut self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        color_type: ColorType,
+    ) -> ImageResult<()> {
+        let pixel_format = pixel_format_from(color_type).ok_or_else(|| {
+            ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                format_hint(),
+                UnsupportedErrorKind::Color(color_type.into()),
+            ))
+        })?;
+
+        let num_channels = color_type.channel_count() as u32;
+        let bits_per_sample = color_type.bytes_per_pixel() as u32 * 8 / num_channels;
+        let exponent_bits = match pixel_format.data_type {
+            JXL_TYPE_FLOAT => 8,
+            _ => 0,
+        };
+        let has_alpha = color_type.has_alpha();
+
+        let basic_info = &mut self.encoder.basic_info;
+        basic_info.xsize = width;
+        basic_info.ysize = height;
+        basic_info.num_color_channels = num_channels - has_alpha as u32;
+        basic_info.bits_per_sample = bits_per_sample;
+        basic_info.exponent_bits_per_sample = exponent_bits;
+        basic_info.num_extra_channels = has_alpha as u32;
+        basic_info.alpha_bits = if has_alpha { bits_per_sample } else { 0 };
+        basic_info.alpha_exponent_bits = if has_alpha { exponent_bits } else { 0 };
+
+        let frame = PixelFrame {
+            data: buf,
+            pixel_format,
+            planar: false,
+        };
+        let encoded = self.encoder.encode_frame(&frame).map_err(encoding_error)?;
+        self.writer.write_all(&encoded)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::frame_delay;
+    use image::Delay;
+    use libjxl_sys::JxlAnimationHeader;
+
+    fn animation(tps_numerator: u32, tps_denominator: u32) -> JxlAnimationHeader {
+        JxlAnimationHeader {
+            tps_numerator,
+            tps_denominator,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn delay_in_ms() {
+        let delay = frame_delay(5, &animation(100, 1));
+        assert_eq!(delay, Delay::from_numer_denom_ms(50, 1));
+        assert_eq!(frame_delay(5, &animation(0, 1)).numer_denom_ms(), (0, 1));
+    }
+
+    #[test]
+    fn delay_reduced_before_overflow() {
+        let delay = frame_delay(5000, &animation(30000, 1001));
+        assert_eq!(delay.numer_denom_ms(), (500500, 3));
+    }
+
+    #[test]
+    fn delay_saturates() {
+        let delay = frame_delay(u32::MAX, &animation(1, u32::MAX));
+        assert_eq!(delay.numer_denom_ms(), (u32::MAX, 1));
+    }
+}
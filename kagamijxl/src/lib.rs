@@ -1,20 +1,63 @@
+#[cfg(feature = "encoder")]
+mod butteraugli;
+pub mod container;
 mod contiguous_buffer;
 mod coupled_bufread;
 mod decode;
+mod downsample;
+#[cfg(feature = "encoder")]
 mod encode;
-pub use decode::{DecodeProgress, Decoder, Frame, JxlDecodeError};
-pub use encode::{BitmapFrame, Encoder, JpegFrame, JxlEncodeError};
+#[cfg(feature = "encoder")]
+mod frame_settings;
+#[cfg(all(feature = "gif", feature = "encoder"))]
+mod gif_codec;
+#[cfg(feature = "image")]
+mod image_codec;
+mod pixel_format;
+#[cfg(feature = "png")]
+mod png_codec;
+pub mod pnm;
+#[cfg(feature = "encoder")]
+pub mod quality;
+mod signature;
+mod version;
+#[cfg(feature = "encoder")]
+pub use butteraugli::{butteraugli_compare, ButteraugliResult, JxlButteraugliError};
+pub use decode::{
+    reconstruct_jpeg, DecodeProgress, Decoder, Frame, ImageOutCallback, JxlDecodeError, Rect,
+    Sample,
+};
+#[cfg(feature = "encoder")]
+pub use encode::{
+    distance_from_quality, BitmapFrame, Encoder, FrameInfo, JpegFrame, JpegOptions, JxlEncodeError,
+    PixelFrame, SizeSearchResult, TargetSize,
+};
+#[cfg(feature = "encoder")]
+pub use frame_settings::FrameSettings;
+#[cfg(all(feature = "gif", feature = "encoder"))]
+pub use gif_codec::GifError;
+#[cfg(feature = "image")]
+pub use image_codec::JxlImageDecoder;
+#[cfg(all(feature = "image", feature = "encoder"))]
+pub use image_codec::JxlImageEncoder;
 pub use libjxl_sys::JxlBasicInfo as BasicInfo;
+pub use libjxl_sys::JxlBlendInfo as BlendInfo;
+pub use libjxl_sys::JxlColorEncoding as ColorEncoding;
+pub use libjxl_sys::JxlPixelFormat as PixelFormat;
+pub use libjxl_sys::JxlVersion as Version;
+#[cfg(feature = "png")]
+pub use png_codec::PngError;
+pub use signature::{check_signature, sniff, Signature};
+#[cfg(feature = "encoder")]
+pub use version::encoder_version;
+pub use version::{decoder_version, MIN_VERSION};
 
 pub fn decode_memory(data: &[u8]) -> Result<DecodeProgress, JxlDecodeError> {
     let decoder = Decoder::default();
     decoder.decode(data)
 }
 
-pub fn check_signature(data: &[u8]) -> libjxl_sys::JxlSignature {
-    unsafe { libjxl_sys::JxlSignatureCheck(data.as_ptr(), data.len()) }
-}
-
+#[cfg(feature = "encoder")]
 pub fn encode_memory(data: &[u8], xsize: usize, ysize: usize) -> Result<Vec<u8>, JxlEncodeError> {
     let mut encoder = Encoder::default();
     encoder.basic_info.xsize = xsize as u32;
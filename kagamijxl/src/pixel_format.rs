@@ -0,0 +1,111 @@
+use libjxl_sys::*;
+
+pub fn bytes_per_sample(data_type: JxlDataType) -> Option<usize> {
+    match data_type {
+        JXL_TYPE_UINT8 => Some(1),
+        JXL_TYPE_UINT16 | JXL_TYPE_FLOAT16 => Some(2),
+        JXL_TYPE_FLOAT => Some(4),
+        _ => None,
+    }
+}
+
+pub fn bytes_per_pixel(pixel_format: &JxlPixelFormat) -> usize {
+    let bytes_per_sample = bytes_per_sample(pixel_format.data_type)
+        .unwrap_or_else(|| panic!("Unexpected JXL data type found: {}", pixel_format.data_type));
+    pixel_format.num_channels as usize * bytes_per_sample
+}
+
+/**
+ * Bytes from the start of a row to the next, which libjxl pads to a multiple of `align`,
+ * or `None` on overflow
+ */
+#[cfg(feature = "encoder")]
+pub fn row_stride(row_size: usize, align: usize) -> Option<usize> {
+    if align <= 1 {
+        Some(row_size)
+    } else {
+        row_size.div_ceil(align).checked_mul(align)
+    }
+}
+
+/**
+ * The minimum buffer size for the given rows, as the last row needs no padding,
+ * or `None` on overflow
+ */
+#[cfg(feature = "encoder")]
+pub fn buffer_size(row_size: usize, stride: usize, ysize: usize) -> Option<usize> {
+    match ysize {
+        0 => Some(0),
+        _ => stride
+            .checked_mul(ysize - 1)
+            .and_then(|size| size.checked_add(row_size)),
+    }
+}
+
+/**
+ * Converts planes, each holding a channel with its rows padded to `stride`,
+ * into tightly packed interleaved pixels, or returns `None` when the result size overflows.
+ * `data` must hold all the planes.
+ */
+#[cfg(feature = "encoder")]
+pub fn interleave(
+    data: &[u8],
+    xsize: usize,
+    ysize: usize,
+    num_channels: usize,
+    sample_size: usize,
+    stride: usize,
+) -> Option<Vec<u8>> {
+    let pixel_size = num_channels.checked_mul(sample_size)?;
+    let size = xsize.checked_mul(ysize)?.checked_mul(pixel_size)?;
+    let mut result = vec![0u8; size];
+    for (index, pixel) in result.chunks_exact_mut(pixel_size).enumerate() {
+        let (x, y) = (index % xsize, index / xsize);
+        for (channel, sample) in pixel.chunks_exact_mut(sample_size).enumerate() {
+            let start = channel * stride * ysize + y * stride + x * sample_size;
+            sample.copy_from_slice(&data[start..start + sample_size]);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(all(test, feature = "encoder"))]
+mod tests {
+    use super::{buffer_size, interleave, row_stride};
+
+    #[test]
+    fn stride_alignment() {
+        assert_eq!(row_stride(18, 0), Some(18));
+        assert_eq!(row_stride(18, 1), Some(18));
+        assert_eq!(row_stride(18, 8), Some(24));
+        assert_eq!(row_stride(24, 8), Some(24));
+        assert_eq!(row_stride(usize::MAX, 8), None);
+    }
+
+    #[test]
+    fn last_row_unpadded() {
+        assert_eq!(buffer_size(18, 24, 3), Some(66));
+        assert_eq!(buffer_size(18, 24, 0), Some(0));
+        assert_eq!(buffer_size(18, usize::MAX, 3), None);
+    }
+
+    #[test]
+    fn interleave_planes() {
+        #[rustfmt::skip]
+        let data = [
+            1, 2, 0,
+            3, 4, 0,
+            5, 6, 0,
+            7, 8, 0,
+        ];
+        let result = interleave(&data, 2, 2, 2, 1, 3).unwrap();
+        assert_eq!(result, [1, 5, 2, 6, 3, 7, 4, 8]);
+    }
+
+    #[test]
+    fn interleave_wide_samples() {
+        let data = [1, 2, 3, 4, 5, 6];
+        let result = interleave(&data, 1, 1, 3, 2, 2).unwrap();
+        assert_eq!(result, [1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,475 @@
+#[cfg(feature = "encoder")]
+use std::io::Read;
+use std::{borrow::Cow, convert::TryFrom, io::Write};
+
+use png::Info;
+#[cfg(feature = "encoder")]
+use png::{BlendOp, DisposeOp, FrameControl, SrgbRenderingIntent, Transformations};
+
+use crate::DecodeProgress;
+#[cfg(feature = "encoder")]
+use crate::{
+    encode::InputFrame, ColorEncoding, Encoder, FrameInfo, JxlEncodeError, PixelFormat, PixelFrame,
+};
+#[cfg(feature = "encoder")]
+use libjxl_sys::*;
+
+#[derive(Debug)]
+pub enum PngError {
+    Decoding(png::DecodingError),
+    Encoding(png::EncodingError),
+    #[cfg(feature = "encoder")]
+    Jxl(JxlEncodeError),
+    Unsupported(String),
+}
+
+impl From<png::DecodingError> for PngError {
+    fn from(err: png::DecodingError) -> Self {
+        PngError::Decoding(err)
+    }
+}
+
+impl From<png::EncodingError> for PngError {
+    fn from(err: png::EncodingError) -> Self {
+        PngError::Encoding(err)
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl From<JxlEncodeError> for PngError {
+    fn from(err: JxlEncodeError) -> Self {
+        PngError::Jxl(err)
+    }
+}
+
+/** Maps sRGB, gAMA and cHRM chunks to a color encoding, or `None` to keep the sRGB default */
+#[cfg(feature = "encoder")]
+fn color_encoding_from(info: &Info, is_gray: bool) -> Option<ColorEncoding> {
+    let mut color_encoding = ColorEncoding::default();
+    if let Some(intent) = info.srgb {
+        unsafe { JxlColorEncodingSetToSRGB(&mut color_encoding, is_gray as _) };
+        color_encoding.rendering_intent = match intent {
+            SrgbRenderingIntent::Perceptual => JXL_RENDERING_INTENT_PERCEPTUAL,
+            SrgbRenderingIntent::RelativeColorimetric => JXL_RENDERING_INTENT_RELATIVE,
+            SrgbRenderingIntent::Saturation => JXL_RENDERING_INTENT_SATURATION,
+            SrgbRenderingIntent::AbsoluteColorimetric => JXL_RENDERING_INTENT_ABSOLUTE,
+        };
+        return Some(color_encoding);
+    }
+    if info.source_gamma.is_none() && info.source_chromaticities.is_none() {
+        return None;
+    }
+
+    unsafe { JxlColorEncodingSetToSRGB(&mut color_encoding, is_gray as _) };
+    if let Some(gamma) = info.source_gamma {
+        // gAMA holds the encoding exponent as libjxl does, e.g. 0.45455 for 2.2
+        let gamma = gamma.into_value() as f64;
+        if (gamma - 1.0).abs() < 1e-4 {
+            color_encoding.transfer_function = JXL_TRANSFER_FUNCTION_LINEAR;
+        } else {
+            color_encoding.transfer_function = JXL_TRANSFER_FUNCTION_GAMMA;
+            color_encoding.gamma = gamma;
+        }
+    }
+    if let Some(chromaticities) = info.source_chromaticities {
+        let xy = |(x, y): (png::ScaledFloat, png::ScaledFloat)| {
+            [x.into_value() as f64, y.into_value() as f64]
+        };
+        color_encoding.white_point = JXL_WHITE_POINT_CUSTOM;
+        color_encoding.white_point_xy = xy(chromaticities.white);
+        if !is_gray {
+            color_encoding.primaries = JXL_PRIMARIES_CUSTOM;
+            color_encoding.primaries_red_xy = xy(chromaticities.red);
+            color_encoding.primaries_green_xy = xy(chromaticities.green);
+            color_encoding.primaries_blue_xy = xy(chromaticities.blue);
+        }
+    }
+    Some(color_encoding)
+}
+
+/** Finds the eXIf chunk data, which the png crate does not read */
+#[cfg(feature = "encoder")]
+fn find_exif(file: &[u8]) -> Option<&[u8]> {
+    // Skips the signature and walks over length, type, data and CRC of each chunk
+    let mut rest = file.get(8..)?;
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let chunk_type = &rest[4..8];
+        let data = rest.get(8..8 + length)?;
+        if chunk_type == b"eXIf" {
+            return Some(data);
+        }
+        rest = rest.get(12 + length..)?;
+    }
+    None
+}
+
+/** Reads a big endian sample of 1 or 2 bytes */
+#[cfg(feature = "encoder")]
+fn read_sample(bytes: &[u8]) -> f64 {
+    match bytes {
+        [value] => *value as f64,
+        [high, low] => u16::from_be_bytes([*high, *low]) as f64,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(feature = "encoder")]
+fn write_sample(bytes: &mut [u8], value: f64) {
+    match bytes {
+        [byte] => *byte = value.round() as u8,
+        [_, _] => bytes.copy_from_slice(&(value.round() as u16).to_be_bytes()),
+        _ => unreachable!(),
+    }
+}
+
+/** Blends a pixel over another with straight alpha, as APNG `APNG_BLEND_OP_OVER` does */
+#[cfg(feature = "encoder")]
+fn blend_pixel(source: &[u8], target: &mut [u8], num_channels: usize, sample_size: usize) {
+    let max = ((1u32 << (8 * sample_size)) - 1) as f64;
+    let alpha_start = (num_channels - 1) * sample_size;
+    let source_alpha = read_sample(&source[alpha_start..]) / max;
+    let target_alpha = read_sample(&target[alpha_start..]) / max;
+
+    let alpha = source_alpha + target_alpha * (1.0 - source_alpha);
+    if alpha == 0.0 {
+        target.iter_mut().for_each(|byte| *byte = 0);
+        return;
+    }
+    for (source, target) in source[..alpha_start]
+        .chunks_exact(sample_size)
+        .zip(target[..alpha_start].chunks_exact_mut(sample_size))
+    {
+        let value = (read_sample(source) * source_alpha
+            + read_sample(target) * target_alpha * (1.0 - source_alpha))
+            / alpha;
+        write_sample(target, value);
+    }
+    write_sample(&mut target[alpha_start..], alpha * max);
+}
+
+/** The canvas that APNG frames are composited on, as JXL frames need no disposal */
+#[cfg(feature = "encoder")]
+struct Canvas {
+    data: Vec<u8>,
+    xsize: usize,
+    num_channels: usize,
+    sample_size: usize,
+    has_alpha: bool,
+}
+
+#[cfg(feature = "encoder")]
+impl Canvas {
+    fn pixel_size(&self) -> usize {
+        self.num_channels * self.sample_size
+    }
+
+    fn region_rows(&self, control: &FrameControl) -> impl Iterator<Item = std::ops::Range<usize>> {
+        let pixel_size = self.pixel_size();
+        let (x0, y0) = (control.x_offset as usize, control.y_offset as usize);
+        let (width, xsize) = (control.width as usize, self.xsize);
+        (y0..y0 + control.height as usize).map(move |y| {
+            let start = (y * xsize + x0) * pixel_size;
+            start..start + width * pixel_size
+        })
+    }
+
+    fn draw(&mut self, frame: &[u8], control: &FrameControl) {
+        let pixel_size = self.pixel_size();
+        let row_size = control.width as usize * pixel_size;
+        let rows: Vec<_> = self.region_rows(control).collect();
+        let blend = self.has_alpha && control.blend_op == BlendOp::Over;
+        let (num_channels, sample_size) = (self.num_channels, self.sample_size);
+
+        for (source_row, range) in frame.chunks_exact(row_size).zip(rows) {
+            let target_row = &mut self.data[range];
+            if !blend {
+                target_row.copy_from_slice(source_row);
+                continue;
+            }
+            for (source, target) in source_row
+                .chunks_exact(pixel_size)
+                .zip(target_row.chunks_exact_mut(pixel_size))
+            {
+                blend_pixel(source, target, num_channels, sample_size);
+            }
+        }
+    }
+
+    fn clear(&mut self, control: &FrameControl) {
+        let rows: Vec<_> = self.region_rows(control).collect();
+        for range in rows {
+            self.data[range].iter_mut().for_each(|byte| *byte = 0);
+        }
+    }
+}
+
+/** Converts an APNG frame delay to milliseconds, where a zero denominator means 1/100 */
+#[cfg(feature = "encoder")]
+fn delay_ms(control: &FrameControl) -> u32 {
+    let denominator = match control.delay_den {
+        0 => 100,
+        denominator => denominator as u32,
+    };
+    (control.delay_num as u32 * 1000 + denominator / 2) / denominator
+}
+
+#[cfg(feature = "encoder")]
+impl Encoder {
+    /**
+     * Encodes a PNG or APNG file, taking its size, pixel format, color encoding and Exif data
+     * instead of the ones specified in this encoder. APNG frames are composited so that each
+     * JXL frame covers the whole canvas.
+     */
+    pub fn encode_png(&self, mut png: impl Read) -> Result<Vec<u8>, PngError> {
+        let mut file = Vec::new();
+        png.read_to_end(&mut file)
+            .map_err(png::DecodingError::from)?;
+
+        let mut decoder = png::Decoder::new(&file[..]);
+        // Expands palettes, tRNS and low bit depths while keeping 16-bit samples
+        decoder.set_transformations(Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+
+        let (color_type, bit_depth) = reader.output_color_type();
+        let num_channels = color_type.samples();
+        let sample_size = match bit_depth {
+            png::BitDepth::Eight => 1,
+            png::BitDepth::Sixteen => 2,
+            _ => {
+                return Err(PngError::Unsupported(format!(
+                    "Bit depth {:?} is unsupported",
+                    bit_depth
+                )))
+            }
+        };
+        let is_gray = num_channels <= 2;
+        let has_alpha = num_channels % 2 == 0;
+
+        let mut enc = self.clone();
+        let info = reader.info();
+        let basic_info = &mut enc.basic_info;
+        basic_info.xsize = info.width;
+        basic_info.ysize = info.height;
+        basic_info.bits_per_sample = sample_size as u32 * 8;
+        basic_info.exponent_bits_per_sample = 0;
+        basic_info.num_color_channels = if is_gray { 1 } else { 3 };
+        basic_info.num_extra_channels = has_alpha as u32;
+        basic_info.alpha_bits = if has_alpha { sample_size as u32 * 8 } else { 0 };
+        basic_info.alpha_exponent_bits = 0;
+
+        enc.color_profile = info.icc_profile.as_ref().map(|profile| profile.to_vec());
+        enc.color_encoding = color_encoding_from(info, is_gray);
+        enc.exif = find_exif(&file).map(|exif| exif.to_vec());
+
+        let pixel_format = PixelFormat {
+            num_channels: num_channels as u32,
+            data_type: if sample_size == 1 {
+                JXL_TYPE_UINT8
+            } else {
+                JXL_TYPE_UINT16
+            },
+            endianness: JXL_BIG_ENDIAN,
+            align: 0,
+        };
+
+        let mut buffer = vec![0u8; reader.output_buffer_size()];
+        let animation = match info.animation_control {
+            Some(animation) => animation,
+            None => {
+                let output = reader.next_frame(&mut buffer)?;
+                buffer.truncate(output.buffer_size());
+                let frame = PixelFrame {
+                    data: &buffer,
+                    pixel_format,
+                    planar: false,
+                };
+                return Ok(enc.encode_frame(&frame)?);
+            }
+        };
+
+        enc.basic_info.have_animation = true as _;
+        enc.basic_info.animation.tps_numerator = 1000;
+        enc.basic_info.animation.tps_denominator = 1;
+        enc.basic_info.animation.num_loops = animation.num_plays;
+
+        // The default image is not a part of the animation unless it has its own fcTL
+        let has_hidden_default = info.frame_control.is_none();
+        if has_hidden_default {
+            reader.next_frame(&mut buffer)?;
+        }
+
+        let mut canvas = Canvas {
+            data: vec![0u8; buffer.len()],
+            xsize: enc.basic_info.xsize as usize,
+            num_channels,
+            sample_size,
+            has_alpha,
+        };
+        let mut frames = Vec::with_capacity(animation.num_frames as usize);
+        for _ in 0..animation.num_frames {
+            reader.next_frame(&mut buffer)?;
+            let control = *reader
+                .info()
+                .frame_control()
+                .expect("APNG frames should have fcTL");
+
+            let previous = match control.dispose_op {
+                DisposeOp::Previous => Some(canvas.data.clone()),
+                _ => None,
+            };
+            canvas.draw(&buffer, &control);
+            frames.push((canvas.data.clone(), delay_ms(&control)));
+
+            match control.dispose_op {
+                DisposeOp::Background => canvas.clear(&control),
+                DisposeOp::Previous => canvas.data = previous.unwrap(),
+                DisposeOp::None => {}
+            }
+        }
+
+        let pixel_frames: Vec<_> = frames
+            .iter()
+            .map(|(data, _)| PixelFrame {
+                data,
+                pixel_format,
+                planar: false,
+            })
+            .collect();
+        let frame_infos: Vec<_> = pixel_frames
+            .iter()
+            .zip(&frames)
+            .map(|(frame, (_, duration))| {
+                let info = FrameInfo {
+                    duration: *duration,
+                    ..Default::default()
+                };
+                (frame as &dyn InputFrame, info)
+            })
+            .collect();
+        Ok(enc.encode_frames(&frame_infos)?)
+    }
+}
+
+impl DecodeProgress {
+    /**
+     * Writes the decoded frames as PNG, or APNG when the image is animated, with the color
+     * profile when `Decoder::need_color_profile` was specified.
+     */
+    pub fn write_png(&self, writer: impl Write) -> Result<(), PngError> {
+        let first = self
+            .frames
+            .first()
+            .ok_or_else(|| PngError::Unsupported("No frame is decoded".to_string()))?;
+        let (xsize, ysize) = (first.xsize, first.ysize);
+        if self
+            .frames
+            .iter()
+            .any(|frame| (frame.xsize, frame.ysize) != (xsize, ysize) || frame.data.is_empty())
+        {
+            return Err(PngError::Unsupported(
+                "Frames must be coalesced and have pixels".to_string(),
+            ));
+        }
+
+        let mut info = Info::with_size(xsize, ysize);
+        info.color_type = png::ColorType::Rgba;
+        info.bit_depth = png::BitDepth::Eight;
+        if !self.color_profile.is_empty() {
+            info.icc_profile = Some(Cow::Borrowed(&self.color_profile));
+        }
+
+        let mut encoder = png::Encoder::with_info(writer, info)?;
+        let animation = self.basic_info.animation;
+        let is_animated = self.basic_info.have_animation != 0 && animation.tps_numerator != 0;
+        if is_animated {
+            encoder.set_animated(self.frames.len() as u32, animation.num_loops)?;
+        }
+
+        let mut writer = encoder.write_header()?;
+        for frame in &self.frames {
+            if is_animated {
+                let ms = frame.duration as u64 * 1000 * animation.tps_denominator as u64
+                    / animation.tps_numerator as u64;
+                match u16::try_from(ms) {
+                    Ok(ms) => writer.set_frame_delay(ms, 1000)?,
+                    Err(_) => writer.set_frame_delay((ms / 1000).min(u16::MAX as u64) as u16, 1)?,
+                }
+            }
+            writer.write_image_data(&frame.data)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "encoder"))]
+mod tests {
+    use super::{blend_pixel, delay_ms, find_exif};
+    use png::{BlendOp, DisposeOp, FrameControl};
+
+    #[test]
+    fn blend_opaque() {
+        let mut target = [10, 20, 30, 255];
+        blend_pixel(&[40, 50, 60, 255], &mut target, 4, 1);
+        assert_eq!(target, [40, 50, 60, 255]);
+    }
+
+    #[test]
+    fn blend_transparent() {
+        let mut target = [10, 20, 30, 255];
+        blend_pixel(&[40, 50, 60, 0], &mut target, 4, 1);
+        assert_eq!(target, [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn blend_half_16bit() {
+        let mut target = [0, 0, 0, 0];
+        blend_pixel(&[0xff, 0xff, 0x80, 0x00], &mut target, 2, 2);
+        assert_eq!(target, [0xff, 0xff, 0x80, 0x00]);
+    }
+
+    fn chunk(chunk_type: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(chunk_type);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    #[test]
+    fn exif() {
+        let mut file = b"\x89PNG\r\n\x1a\n".to_vec();
+        file.extend(chunk(b"IHDR", &[0; 13]));
+        file.extend(chunk(b"eXIf", b"MM\0\x2a"));
+        file.extend(chunk(b"IEND", &[]));
+        assert_eq!(find_exif(&file), Some(&b"MM\0\x2a"[..]));
+    }
+
+    #[test]
+    fn no_exif() {
+        let mut file = b"\x89PNG\r\n\x1a\n".to_vec();
+        file.extend(chunk(b"IHDR", &[0; 13]));
+        file.extend(chunk(b"IEND", &[]));
+        assert_eq!(find_exif(&file), None);
+        assert_eq!(find_exif(&file[..20]), None);
+    }
+
+    #[test]
+    fn delay() {
+        let mut control = FrameControl {
+            sequence_number: 0,
+            width: 1,
+            height: 1,
+            x_offset: 0,
+            y_offset: 0,
+            delay_num: 1,
+            delay_den: 0,
+            dispose_op: DisposeOp::None,
+            blend_op: BlendOp::Source,
+        };
+        assert_eq!(delay_ms(&control), 10);
+        control.delay_den = 3;
+        assert_eq!(delay_ms(&control), 333);
+    }
+}
@@ -0,0 +1,528 @@
+/*!
+ * Reads and writes PGM, PPM, PAM and PFM images, whose pixels can be encoded as `PixelFrame`s
+ * and decoded back by `Decoder::decode_into`.
+ */
+
+use std::io::{Read, Write};
+
+use crate::PixelFormat;
+#[cfg(feature = "encoder")]
+use crate::PixelFrame;
+use libjxl_sys::*;
+
+#[derive(Debug)]
+pub enum PnmError {
+    Io(std::io::Error),
+    /** The file is not a well-formed PNM file */
+    Invalid(String),
+    /** The file or the image is valid but cannot be handled */
+    Unsupported(String),
+}
+
+impl From<std::io::Error> for PnmError {
+    fn from(err: std::io::Error) -> Self {
+        PnmError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleType {
+    U8,
+    U16,
+    F32,
+}
+
+impl SampleType {
+    pub fn size(self) -> usize {
+        match self {
+            SampleType::U8 => 1,
+            SampleType::U16 => 2,
+            SampleType::F32 => 4,
+        }
+    }
+
+    fn data_type(self) -> JxlDataType {
+        match self {
+            SampleType::U8 => JXL_TYPE_UINT8,
+            SampleType::U16 => JXL_TYPE_UINT16,
+            SampleType::F32 => JXL_TYPE_FLOAT,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PnmImage {
+    pub xsize: u32,
+    pub ysize: u32,
+    /** 1 for gray, 2 for gray and alpha, 3 for RGB, or 4 for RGBA */
+    pub num_channels: u32,
+    pub sample_type: SampleType,
+    /** Interleaved samples in native endian, from the top row to the bottom */
+    pub data: Vec<u8>,
+}
+
+impl PnmImage {
+    pub fn pixel_format(&self) -> PixelFormat {
+        PixelFormat {
+            num_channels: self.num_channels,
+            data_type: self.sample_type.data_type(),
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 0,
+        }
+    }
+
+    /** Borrows the pixels as a frame for `Encoder::encode_frame` */
+    #[cfg(feature = "encoder")]
+    pub fn as_frame(&self) -> PixelFrame<'_> {
+        PixelFrame {
+            data: &self.data,
+            pixel_format: self.pixel_format(),
+            planar: false,
+        }
+    }
+
+    /** Wraps 8-bit RGBA pixels such as `Frame::data` */
+    pub fn from_rgba8(xsize: u32, ysize: u32, data: Vec<u8>) -> Self {
+        Self {
+            xsize,
+            ysize,
+            num_channels: 4,
+            sample_type: SampleType::U8,
+            data,
+        }
+    }
+}
+
+/** Splits a header into tokens, skipping whitespace and comments */
+struct HeaderReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.data.get(self.position) {
+            match byte {
+                b'#' => {
+                    while !matches!(self.data.get(self.position), None | Some(b'\n')) {
+                        self.position += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<&'a str, PnmError> {
+        self.skip_whitespace();
+        let start = self.position;
+        while matches!(self.data.get(self.position), Some(byte) if !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.position])
+            .ok()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| PnmError::Invalid("Header is truncated".to_string()))
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, PnmError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| PnmError::Invalid(format!("Unexpected header value {}", token)))
+    }
+
+    /** Skips the single whitespace that separates the header from the pixels */
+    fn pixels(self) -> &'a [u8] {
+        &self.data[(self.position + 1).min(self.data.len())..]
+    }
+
+    fn skip_line(&mut self) {
+        while !matches!(self.data.get(self.position), None | Some(b'\n')) {
+            self.position += 1;
+        }
+        self.position += 1;
+    }
+}
+
+/** Returns the size of the pixels in bytes, failing when the image is empty or too large */
+fn image_size(
+    xsize: u32,
+    ysize: u32,
+    num_channels: u32,
+    sample_type: SampleType,
+) -> Result<usize, PnmError> {
+    if xsize == 0 || ysize == 0 {
+        return Err(PnmError::Invalid(format!(
+            "Image size {}x{} is empty",
+            xsize, ysize
+        )));
+    }
+    (xsize as usize)
+        .checked_mul(ysize as usize)
+        .and_then(|size| size.checked_mul(num_channels as usize))
+        .and_then(|size| size.checked_mul(sample_type.size()))
+        .ok_or_else(|| {
+            PnmError::Unsupported(format!("Image size {}x{} is too large", xsize, ysize))
+        })
+}
+
+/** Converts big endian integer samples to native endian, scaling them to the full range */
+fn read_samples(
+    data: &[u8],
+    xsize: u32,
+    ysize: u32,
+    num_channels: u32,
+    max_value: u32,
+) -> Result<(SampleType, Vec<u8>), PnmError> {
+    let sample_type = if max_value < 256 {
+        SampleType::U8
+    } else {
+        SampleType::U16
+    };
+    let size = image_size(xsize, ysize, num_channels, sample_type)?;
+    if data.len() < size {
+        return Err(PnmError::Invalid("Pixel data is truncated".to_string()));
+    }
+
+    let data = &data[..size];
+    let samples = match sample_type {
+        SampleType::U8 if max_value == 255 => data.to_vec(),
+        SampleType::U8 => data
+            .iter()
+            .map(|&value| ((value as u32 * 255 + max_value / 2) / max_value).min(255) as u8)
+            .collect(),
+        _ => data
+            .chunks_exact(2)
+            .flat_map(|bytes| {
+                let value = u16::from_be_bytes([bytes[0], bytes[1]]) as u32;
+                let value = ((value * 65535 + max_value / 2) / max_value).min(65535) as u16;
+                value.to_ne_bytes()
+            })
+            .collect(),
+    };
+    Ok((sample_type, samples))
+}
+
+fn read_pnm(mut header: HeaderReader, num_channels: u32) -> Result<PnmImage, PnmError> {
+    let xsize = header.number()?;
+    let ysize = header.number()?;
+    let max_value: u32 = header.number()?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(PnmError::Invalid(format!(
+            "Invalid maximum value {}",
+            max_value
+        )));
+    }
+
+    let (sample_type, data) = read_samples(header.pixels(), xsize, ysize, num_channels, max_value)?;
+    Ok(PnmImage {
+        xsize,
+        ysize,
+        num_channels,
+        sample_type,
+        data,
+    })
+}
+
+fn read_pam(mut header: HeaderReader) -> Result<PnmImage, PnmError> {
+    let (mut xsize, mut ysize, mut depth, mut max_value) = (None, None, None, None);
+    loop {
+        match header.token()? {
+            "WIDTH" => xsize = Some(header.number()?),
+            "HEIGHT" => ysize = Some(header.number()?),
+            "DEPTH" => depth = Some(header.number()?),
+            "MAXVAL" => max_value = Some(header.number()?),
+            "TUPLTYPE" => header.skip_line(),
+            "ENDHDR" => break,
+            token => return Err(PnmError::Invalid(format!("Unexpected header {}", token))),
+        }
+    }
+
+    let missing = |name: &str| PnmError::Invalid(format!("{} is missing", name));
+    let xsize: u32 = xsize.ok_or_else(|| missing("WIDTH"))?;
+    let ysize: u32 = ysize.ok_or_else(|| missing("HEIGHT"))?;
+    let num_channels: u32 = depth.ok_or_else(|| missing("DEPTH"))?;
+    let max_value: u32 = max_value.ok_or_else(|| missing("MAXVAL"))?;
+    if !(1..=4).contains(&num_channels) {
+        return Err(PnmError::Unsupported(format!(
+            "Depth {} is unsupported",
+            num_channels
+        )));
+    }
+    if max_value == 0 || max_value > 65535 {
+        return Err(PnmError::Invalid(format!(
+            "Invalid maximum value {}",
+            max_value
+        )));
+    }
+
+    let (sample_type, data) = read_samples(header.pixels(), xsize, ysize, num_channels, max_value)?;
+    Ok(PnmImage {
+        xsize,
+        ysize,
+        num_channels,
+        sample_type,
+        data,
+    })
+}
+
+fn read_pfm(mut header: HeaderReader, num_channels: u32) -> Result<PnmImage, PnmError> {
+    let xsize: u32 = header.number()?;
+    let ysize: u32 = header.number()?;
+    let scale: f32 = header.number()?;
+
+    let size = image_size(xsize, ysize, num_channels, SampleType::F32)?;
+    let row_size = size / ysize as usize;
+    let data = header.pixels();
+    if data.len() < size {
+        return Err(PnmError::Invalid("Pixel data is truncated".to_string()));
+    }
+
+    // Rows are stored from the bottom, and the sign of the scale tells the endianness
+    let mut result = Vec::with_capacity(size);
+    for row in data.chunks_exact(row_size).take(ysize as usize).rev() {
+        for bytes in row.chunks_exact(4) {
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            let value = if scale < 0.0 {
+                f32::from_le_bytes(bytes)
+            } else {
+                f32::from_be_bytes(bytes)
+            };
+            result.extend_from_slice(&value.to_ne_bytes());
+        }
+    }
+
+    Ok(PnmImage {
+        xsize,
+        ysize,
+        num_channels,
+        sample_type: SampleType::F32,
+        data: result,
+    })
+}
+
+/** Reads a binary PGM (P5), PPM (P6), PAM (P7) or PFM (Pf or PF) image */
+pub fn read(mut reader: impl Read) -> Result<PnmImage, PnmError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let mut header = HeaderReader {
+        data: &data,
+        position: 0,
+    };
+    match header.token()? {
+        "P5" => read_pnm(header, 1),
+        "P6" => read_pnm(header, 3),
+        "P7" => read_pam(header),
+        "Pf" => read_pfm(header, 1),
+        "PF" => read_pfm(header, 3),
+        magic => Err(PnmError::Unsupported(format!(
+            "Magic number {} is unsupported",
+            magic
+        ))),
+    }
+}
+
+/**
+ * Writes `image` as PGM or PPM when possible, PAM when it has alpha,
+ * or PFM when the samples are floats.
+ */
+pub fn write(image: &PnmImage, mut writer: impl Write) -> Result<(), PnmError> {
+    let PnmImage {
+        xsize,
+        ysize,
+        num_channels,
+        sample_type,
+        ..
+    } = *image;
+    let size = image_size(xsize, ysize, num_channels, sample_type)?;
+    if image.data.len() < size {
+        return Err(PnmError::Invalid("Pixel data is truncated".to_string()));
+    }
+    let data = &image.data[..size];
+
+    if sample_type == SampleType::F32 {
+        let magic = match num_channels {
+            1 => "Pf",
+            3 => "PF",
+            _ => {
+                return Err(PnmError::Unsupported(format!(
+                    "PFM cannot have {} channels",
+                    num_channels
+                )))
+            }
+        };
+        // Negative scale means little endian
+        write!(writer, "{}\n{} {}\n-1.0\n", magic, xsize, ysize)?;
+        for row in data.chunks_exact(size / ysize as usize).rev() {
+            for bytes in row.chunks_exact(4) {
+                let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        return Ok(());
+    }
+
+    let max_value = match sample_type {
+        SampleType::U8 => 255,
+        _ => 65535,
+    };
+    match num_channels {
+        1 => write!(writer, "P5\n{} {}\n{}\n", xsize, ysize, max_value)?,
+        3 => write!(writer, "P6\n{} {}\n{}\n", xsize, ysize, max_value)?,
+        2 | 4 => {
+            let tuple_type = match num_channels {
+                2 => "GRAYSCALE_ALPHA",
+                _ => "RGB_ALPHA",
+            };
+            write!(
+                writer,
+                "P7\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL {}\nTUPLTYPE {}\nENDHDR\n",
+                xsize, ysize, num_channels, max_value, tuple_type
+            )?;
+        }
+        _ => {
+            return Err(PnmError::Unsupported(format!(
+                "Channel count {} is unsupported",
+                num_channels
+            )))
+        }
+    }
+
+    match sample_type {
+        SampleType::U8 => writer.write_all(data)?,
+        _ => {
+            let samples: Vec<u8> = data
+                .chunks_exact(2)
+                .flat_map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]).to_be_bytes())
+                .collect();
+            writer.write_all(&samples)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, write, PnmImage, SampleType};
+
+    fn roundtrip(image: &PnmImage) -> PnmImage {
+        let mut file = Vec::new();
+        write(image, &mut file).expect("Failed to write");
+        read(&file[..]).expect("Failed to read")
+    }
+
+    #[test]
+    fn read_ppm_with_comments() {
+        let file = b"P6 # comment\n2 1\n# another\n255\n\x01\x02\x03\x04\x05\x06";
+        let image = read(&file[..]).expect("Failed to read");
+        assert_eq!((image.xsize, image.ysize, image.num_channels), (2, 1, 3));
+        assert_eq!(image.sample_type, SampleType::U8);
+        assert_eq!(image.data, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn read_pgm_scaled() {
+        let file = b"P5\n2 1\n15\n\x00\x0f";
+        let image = read(&file[..]).expect("Failed to read");
+        assert_eq!(image.data, [0, 255]);
+    }
+
+    #[test]
+    fn read_pgm_16bit() {
+        let file = b"P5\n1 1\n65535\n\x12\x34";
+        let image = read(&file[..]).expect("Failed to read");
+        assert_eq!(image.sample_type, SampleType::U16);
+        assert_eq!(image.data, 0x1234u16.to_ne_bytes());
+    }
+
+    #[test]
+    fn read_pfm_bottom_up() {
+        let mut file = b"Pf\n1 2\n1.0\n".to_vec();
+        file.extend_from_slice(&1.0f32.to_be_bytes());
+        file.extend_from_slice(&2.0f32.to_be_bytes());
+        let image = read(&file[..]).expect("Failed to read");
+
+        let mut expected = 2.0f32.to_ne_bytes().to_vec();
+        expected.extend_from_slice(&1.0f32.to_ne_bytes());
+        assert_eq!(image.data, expected);
+    }
+
+    #[test]
+    fn read_truncated() {
+        assert!(read(&b"P6\n2 2\n255\n\x00"[..]).is_err());
+        assert!(read(&b"P6\n2"[..]).is_err());
+    }
+
+    #[test]
+    fn read_empty_or_too_large() {
+        assert!(read(&b"PF\n0 1\n1.0\n"[..]).is_err());
+        assert!(read(&b"P5\n1 0\n255\n"[..]).is_err());
+        assert!(read(
+            &b"P7\nWIDTH 4294967295\nHEIGHT 4294967295\nDEPTH 4\nMAXVAL 65535\nENDHDR\n"[..]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn roundtrip_pam_rgba16() {
+        let data: Vec<u8> = (0..4u16)
+            .flat_map(|value| (value * 1000).to_ne_bytes())
+            .collect();
+        let image = PnmImage {
+            xsize: 1,
+            ysize: 1,
+            num_channels: 4,
+            sample_type: SampleType::U16,
+            data,
+        };
+        let result = roundtrip(&image);
+        assert_eq!(result.num_channels, 4);
+        assert_eq!(result.sample_type, SampleType::U16);
+        assert_eq!(result.data, image.data);
+    }
+
+    #[test]
+    fn roundtrip_pfm_rgb() {
+        let data: Vec<u8> = [0.0f32, 0.25, 0.5, 0.75, 1.0, 1.25]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        let image = PnmImage {
+            xsize: 1,
+            ysize: 2,
+            num_channels: 3,
+            sample_type: SampleType::F32,
+            data,
+        };
+        let result = roundtrip(&image);
+        assert_eq!(result.sample_type, SampleType::F32);
+        assert_eq!(result.data, image.data);
+    }
+
+    #[test]
+    fn write_unsupported() {
+        let image = PnmImage {
+            xsize: 1,
+            ysize: 1,
+            num_channels: 2,
+            sample_type: SampleType::F32,
+            data: vec![0; 8],
+        };
+        assert!(write(&image, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn write_empty() {
+        let image = PnmImage {
+            xsize: 0,
+            ysize: 1,
+            num_channels: 3,
+            sample_type: SampleType::F32,
+            data: Vec::new(),
+        };
+        assert!(write(&image, Vec::new()).is_err());
+    }
+}
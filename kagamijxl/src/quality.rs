@@ -0,0 +1,158 @@
+/*!
+ * Measures how an `Encoder` performs on an image by encoding it, decoding it back
+ * and comparing the result with the input.
+ */
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    butteraugli_compare, decode_memory, Encoder, Frame, JxlButteraugliError, JxlDecodeError,
+    JxlEncodeError, PixelFormat,
+};
+use libjxl_sys::*;
+
+/** The norm used for `QualityReport::butteraugli_pnorm`, same as the one `cjxl` reports */
+pub const BUTTERAUGLI_PNORM: f32 = 3.0;
+
+#[derive(Debug)]
+pub enum QualityError {
+    Encode(JxlEncodeError),
+    Decode(JxlDecodeError),
+    Butteraugli(JxlButteraugliError),
+    /** The input is not 8-bit RGBA pixels of the size in `Encoder::basic_info` */
+    UnsupportedValue(String),
+}
+
+impl From<JxlEncodeError> for QualityError {
+    fn from(err: JxlEncodeError) -> Self {
+        QualityError::Encode(err)
+    }
+}
+
+impl From<JxlDecodeError> for QualityError {
+    fn from(err: JxlDecodeError) -> Self {
+        QualityError::Decode(err)
+    }
+}
+
+impl From<JxlButteraugliError> for QualityError {
+    fn from(err: JxlButteraugliError) -> Self {
+        QualityError::Butteraugli(err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QualityReport {
+    /** Size of the encoded image in bytes */
+    pub size: usize,
+    pub bits_per_pixel: f64,
+    /** The largest butteraugli distance over all pixels */
+    pub butteraugli_max: f32,
+    /** The `BUTTERAUGLI_PNORM`-norm of the butteraugli distances */
+    pub butteraugli_pnorm: f32,
+    /** PSNR of the color channels in decibels, which is infinite for identical images */
+    pub psnr: f64,
+    pub encode_time: Duration,
+    pub decode_time: Duration,
+}
+
+/**
+ * Encodes `data` as `Encoder::encode` does, decodes it back and reports the cost and the quality
+ * of the round trip. Only 8-bit RGBA is supported, as that is what the decoded pixels are
+ * compared in, so `data` must hold exactly `xsize * ysize * 4` bytes.
+ */
+pub fn evaluate(encoder: &Encoder, data: &[u8]) -> Result<QualityReport, QualityError> {
+    let (xsize, ysize) = (encoder.basic_info.xsize, encoder.basic_info.ysize);
+    let expected_size = (xsize as usize)
+        .checked_mul(ysize as usize)
+        .and_then(|pixels| pixels.checked_mul(4));
+    if expected_size != Some(data.len()) {
+        return Err(QualityError::UnsupportedValue(format!(
+            "{} bytes are not 8-bit RGBA pixels of {}x{}",
+            data.len(),
+            xsize,
+            ysize
+        )));
+    }
+
+    let start = Instant::now();
+    let encoded = encoder.encode(data)?;
+    let encode_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut decoded = decode_memory(&encoded)?;
+    let decode_time = start.elapsed();
+
+    let reference = Frame {
+        xsize,
+        ysize,
+        data: data.to_vec(),
+        ..Default::default()
+    };
+    let distorted = decoded.frames.remove(0);
+    let pixel_format = PixelFormat {
+        num_channels: 4,
+        data_type: JXL_TYPE_UINT8,
+        endianness: JXL_NATIVE_ENDIAN,
+        align: 0,
+    };
+    let result = butteraugli_compare(&reference, &distorted, &pixel_format)?;
+
+    let pixels = xsize as f64 * ysize as f64;
+    Ok(QualityReport {
+        size: encoded.len(),
+        bits_per_pixel: encoded.len() as f64 * 8.0 / pixels,
+        butteraugli_max: result.max_distance(),
+        butteraugli_pnorm: result.distance(BUTTERAUGLI_PNORM),
+        psnr: psnr(&reference.data, &distorted.data, 4, 3),
+        encode_time,
+        decode_time,
+    })
+}
+
+/** PSNR of 8-bit samples, counting only the first `num_color_channels` of each pixel */
+fn psnr(reference: &[u8], distorted: &[u8], num_channels: usize, num_color_channels: usize) -> f64 {
+    let mut squared_error = 0u64;
+    let mut count = 0u64;
+    let pixels = reference
+        .chunks_exact(num_channels)
+        .zip(distorted.chunks_exact(num_channels));
+    for (a, b) in pixels {
+        for (x, y) in a.iter().zip(b).take(num_color_channels) {
+            let diff = *x as i64 - *y as i64;
+            squared_error += (diff * diff) as u64;
+            count += 1;
+        }
+    }
+
+    if squared_error == 0 {
+        return f64::INFINITY;
+    }
+    let mse = squared_error as f64 / count as f64;
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::psnr;
+
+    #[test]
+    fn identical() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(psnr(&data, &data, 4, 3), f64::INFINITY);
+    }
+
+    #[test]
+    fn ignores_alpha() {
+        let reference = [0, 0, 0, 0];
+        let distorted = [0, 0, 0, 255];
+        assert_eq!(psnr(&reference, &distorted, 4, 3), f64::INFINITY);
+    }
+
+    #[test]
+    fn max_error() {
+        let reference = [0, 0, 0, 255];
+        let distorted = [255, 255, 255, 255];
+        assert_eq!(psnr(&reference, &distorted, 4, 3), 0.0);
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::Read;
+
+use libjxl_sys::*;
+
+/** The longest signature, which is the `JXL ` box starting a container */
+const MAX_SIGNATURE_SIZE: usize = 12;
+
+/** What the first bytes of a file say about whether it is a JPEG XL image */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signature {
+    /** More bytes are needed to decide */
+    NotEnoughBytes,
+    /** The data is not a JPEG XL image */
+    Invalid,
+    /** A bare JPEG XL codestream */
+    Codestream,
+    /** A JPEG XL codestream wrapped in the ISOBMFF-based container */
+    Container,
+}
+
+pub fn check_signature(data: &[u8]) -> Signature {
+    match unsafe { JxlSignatureCheck(data.as_ptr(), data.len()) } {
+        JXL_SIG_NOT_ENOUGH_BYTES => Signature::NotEnoughBytes,
+        JXL_SIG_CODESTREAM => Signature::Codestream,
+        JXL_SIG_CONTAINER => Signature::Container,
+        _ => Signature::Invalid,
+    }
+}
+
+/**
+ * Reads only as many bytes as needed to check the signature, which is at most 12.
+ * Returns `NotEnoughBytes` if the reader ends before that.
+ */
+pub fn sniff(reader: impl Read) -> std::io::Result<Signature> {
+    let mut data = Vec::with_capacity(MAX_SIGNATURE_SIZE);
+    let mut reader = reader.take(MAX_SIGNATURE_SIZE as u64);
+    loop {
+        let signature = check_signature(&data);
+        if signature != Signature::NotEnoughBytes {
+            return Ok(signature);
+        }
+
+        // Reads byte by byte so that nothing after the signature is consumed
+        let mut byte = [0u8];
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(signature),
+            Ok(_) => data.push(byte[0]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Once,
+};
+
+use crate::Version;
+
+/** The oldest libjxl whose API and behavior kagamijxl relies on */
+pub const MIN_VERSION: Version = Version::new(0, 7, 0);
+
+/** The version of the linked libjxl decoder */
+pub fn decoder_version() -> Version {
+    Version::decoder()
+}
+
+/** The version of the linked libjxl encoder */
+#[cfg(feature = "encoder")]
+pub fn encoder_version() -> Version {
+    Version::encoder()
+}
+
+/** Queries the version only on the first call, and returns it afterwards if it is too old */
+fn check_once(
+    once: &Once,
+    supported: &AtomicBool,
+    version: fn() -> Version,
+) -> Result<(), Version> {
+    once.call_once(|| supported.store(version() >= MIN_VERSION, Ordering::Relaxed));
+    match supported.load(Ordering::Relaxed) {
+        true => Ok(()),
+        false => Err(version()),
+    }
+}
+
+/** Fails with the linked version when the decoder is older than `MIN_VERSION` */
+pub(crate) fn check_decoder_version() -> Result<(), Version> {
+    static ONCE: Once = Once::new();
+    static SUPPORTED: AtomicBool = AtomicBool::new(false);
+    check_once(&ONCE, &SUPPORTED, decoder_version)
+}
+
+/** Fails with the linked version when the encoder is older than `MIN_VERSION` */
+#[cfg(feature = "encoder")]
+pub(crate) fn check_encoder_version() -> Result<(), Version> {
+    static ONCE: Once = Once::new();
+    static SUPPORTED: AtomicBool = AtomicBool::new(false);
+    check_once(&ONCE, &SUPPORTED, encoder_version)
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "encoder")]
+
+use kagamijxl::{
+    butteraugli_compare, decode_memory, Encoder, Frame, JxlButteraugliError, PixelFormat,
+};
+use libjxl_sys::{JXL_NATIVE_ENDIAN, JXL_TYPE_UINT8};
+
+const RGBA8: PixelFormat = PixelFormat {
+    num_channels: 4,
+    data_type: JXL_TYPE_UINT8,
+    endianness: JXL_NATIVE_ENDIAN,
+    align: 0,
+};
+
+fn get_sample_frame() -> Frame {
+    let path =
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/sample.jxl");
+    let data = std::fs::read(path).expect("Failed to read the sample image");
+    let mut result = decode_memory(&data).expect("Failed to decode the sample image");
+    result.frames.remove(0)
+}
+
+fn reencode(frame: &Frame, distance: f32) -> Frame {
+    let mut encoder = Encoder::default();
+    encoder.distance = Some(distance);
+    encoder.effort = Some(3);
+    encoder.basic_info.xsize = frame.xsize;
+    encoder.basic_info.ysize = frame.ysize;
+    let encoded = encoder.encode(&frame.data).expect("Failed to encode");
+
+    let mut result = decode_memory(&encoded).expect("Failed to decode again");
+    result.frames.remove(0)
+}
+
+#[test]
+fn test_butteraugli_identical() {
+    let frame = get_sample_frame();
+    let result = butteraugli_compare(&frame, &frame, &RGBA8).expect("Failed to compare");
+    assert_eq!(result.max_distance(), 0.0);
+}
+
+#[test]
+fn test_butteraugli_distorted() {
+    let reference = get_sample_frame();
+    let slightly = reencode(&reference, 1.0);
+    let heavily = reencode(&reference, 8.0);
+
+    let slight_result = butteraugli_compare(&reference, &slightly, &RGBA8).expect("Failed");
+    let heavy_result = butteraugli_compare(&reference, &heavily, &RGBA8).expect("Failed");
+    assert!(slight_result.max_distance() > 0.0);
+    assert!(slight_result.max_distance() < heavy_result.max_distance());
+    assert!(heavy_result.distance(3.0) <= heavy_result.max_distance());
+}
+
+#[test]
+fn test_butteraugli_distmap() {
+    let reference = get_sample_frame();
+    let distorted = reencode(&reference, 4.0);
+
+    let result = butteraugli_compare(&reference, &distorted, &RGBA8).expect("Failed to compare");
+    let distmap = result.distmap();
+    assert_eq!(distmap.len(), (reference.xsize * reference.ysize) as usize);
+
+    let max = distmap.iter().cloned().fold(0.0, f32::max);
+    assert!((max - result.max_distance()).abs() < 1e-3);
+}
+
+#[test]
+fn test_butteraugli_size_mismatch() {
+    let reference = get_sample_frame();
+    let distorted = Frame {
+        xsize: reference.xsize - 1,
+        ysize: reference.ysize,
+        data: reference.data.clone(),
+        ..Default::default()
+    };
+
+    let err = butteraugli_compare(&reference, &distorted, &RGBA8).err();
+    assert!(matches!(err, Some(JxlButteraugliError::SizeMismatch)));
+}
+
+#[test]
+fn test_butteraugli_unsupported_data_type() {
+    let frame = get_sample_frame();
+    let pixel_format = PixelFormat {
+        data_type: 99,
+        ..RGBA8
+    };
+
+    let err = butteraugli_compare(&frame, &frame, &pixel_format).err();
+    assert!(matches!(
+        err,
+        Some(JxlButteraugliError::UnsupportedValue(_))
+    ));
+}
+
+#[test]
+fn test_butteraugli_padded_rows() {
+    let frame = get_sample_frame();
+    let pixel_format = PixelFormat { align: 64, ..RGBA8 };
+
+    // Each row of 1404 RGBA pixels is padded from 5616 to 5632 bytes
+    let row_size = frame.xsize as usize * 4;
+    let mut data = Vec::new();
+    for row in frame.data.chunks_exact(row_size) {
+        data.extend_from_slice(row);
+        data.resize(data.len() + 16, 0);
+    }
+    let padded = Frame {
+        xsize: frame.xsize,
+        ysize: frame.ysize,
+        data,
+        ..Default::default()
+    };
+
+    let result = butteraugli_compare(&padded, &padded, &pixel_format).expect("Failed to compare");
+    assert_eq!(result.max_distance(), 0.0);
+
+    let err = butteraugli_compare(&frame, &frame, &pixel_format).err();
+    assert!(matches!(err, Some(JxlButteraugliError::BufferTooSmall)));
+}
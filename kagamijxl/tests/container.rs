@@ -0,0 +1,85 @@
+use kagamijxl::{container, reconstruct_jpeg, Decoder};
+#[cfg(feature = "encoder")]
+use kagamijxl::{container::ContainerError, decode_memory, Encoder};
+use std::path::PathBuf;
+
+#[cfg(feature = "encoder")]
+fn encode_with_exif() -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 1;
+    encoder.basic_info.ysize = 1;
+    encoder.exif = Some(b"MM\0\x2a\0\0\0\x08\0\0".to_vec());
+    encoder.encode(&[1, 2, 3, 4]).expect("Failed to encode")
+}
+
+#[test]
+#[cfg(feature = "encoder")]
+fn test_container_read_boxes() {
+    let data = encode_with_exif();
+    let boxes = container::read_boxes(&data).expect("Failed to read boxes");
+
+    assert_eq!(&boxes[0].box_type, b"JXL ");
+    assert!(boxes.iter().any(|jxl_box| jxl_box.is_metadata()));
+    let end = boxes.last().map(|last| last.offset + last.size);
+    assert_eq!(end, Some(data.len()));
+}
+
+#[test]
+#[cfg(feature = "encoder")]
+fn test_container_strip_metadata() {
+    let data = encode_with_exif();
+    let stripped = container::strip_metadata(&data).expect("Failed to strip");
+    assert!(stripped.len() < data.len());
+
+    let boxes = container::read_boxes(&stripped).expect("Failed to read boxes");
+    assert!(boxes.iter().all(|jxl_box| !jxl_box.is_metadata()));
+
+    let original = decode_memory(&data).expect("Failed to decode");
+    let result = decode_memory(&stripped).expect("Failed to decode the stripped image");
+    assert_eq!(result.frames[0].data, original.frames[0].data);
+}
+
+#[test]
+fn test_container_strip_sample_metadata() {
+    let sample_path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/needmoreinput.jxl");
+    let data = std::fs::read(sample_path).expect("Failed to read");
+    let stripped = container::strip_metadata(&data).expect("Failed to strip");
+
+    let boxes = container::read_boxes(&stripped).expect("Failed to read boxes");
+    let types: Vec<_> = boxes.iter().map(|jxl_box| &jxl_box.box_type).collect();
+    assert_eq!(types, [b"JXL ", b"ftyp", b"jxlc"]);
+
+    // The reconstruction data referred to the stripped Exif and XMP boxes
+    assert!(matches!(reconstruct_jpeg(&data), Ok(Some(_))));
+    assert!(matches!(reconstruct_jpeg(&stripped), Ok(None)));
+
+    let decoder = Decoder {
+        no_full_image: true,
+        ..Default::default()
+    };
+    let result = decoder
+        .decode(&stripped)
+        .expect("Failed to decode the stripped image");
+    assert_eq!(result.frames.len(), 1);
+}
+
+#[test]
+#[cfg(feature = "encoder")]
+fn test_container_wrap_codestream() {
+    let codestream = kagamijxl::encode_memory(&[1, 2, 3, 4], 1, 1).expect("Failed to encode");
+    assert!(matches!(
+        container::read_boxes(&codestream),
+        Err(ContainerError::Invalid(_))
+    ));
+
+    let data =
+        container::insert_box(&codestream, b"xml ", b"<x:xmpmeta/>").expect("Failed to insert");
+    let boxes = container::read_boxes(&data).expect("Failed to read boxes");
+    let types: Vec<_> = boxes.iter().map(|jxl_box| &jxl_box.box_type).collect();
+    assert_eq!(types, [b"JXL ", b"ftyp", b"xml ", b"jxlc"]);
+
+    let original = decode_memory(&codestream).expect("Failed to decode");
+    let result = decode_memory(&data).expect("Failed to decode the container");
+    assert_eq!(result.frames[0].data, original.frames[0].data);
+}
@@ -1,6 +1,12 @@
-use std::{fs::File, io::BufReader, path::PathBuf};
-
-use kagamijxl::{decode_memory, Decoder, JxlDecodeError};
+use std::{
+    fs::File,
+    io::BufReader,
+    panic::AssertUnwindSafe,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use kagamijxl::{decode_memory, Decoder, JxlDecodeError, Rect};
 use libjxl_sys::JXL_ORIENT_IDENTITY;
 
 const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
@@ -109,6 +115,235 @@ fn test_decode_color_profile() {
     assert_ne!(result.color_profile.len(), 0);
 }
 
+#[test]
+fn test_decode_crop() {
+    let data = get_sample_image();
+
+    let full = decode_memory(&data).expect("Failed to decode the sample image");
+
+    let mut decoder = Decoder::default();
+    decoder.crop = Some(Rect {
+        x0: 100,
+        y0: 50,
+        xsize: 64,
+        ysize: 32,
+    });
+
+    let result = decoder
+        .decode(&data)
+        .expect("Failed to decode the sample image");
+    let frame = &result.frames[0];
+
+    assert_eq!(frame.xsize, 64);
+    assert_eq!(frame.ysize, 32);
+    assert_eq!(frame.data.len(), 64 * 32 * 4);
+
+    let full_stride = full.basic_info.xsize as usize * 4;
+    for y in 0..32 {
+        let start = (50 + y) * full_stride + 100 * 4;
+        let row = &frame.data[y * 64 * 4..(y + 1) * 64 * 4];
+        assert_eq!(row, &full.frames[0].data[start..start + 64 * 4]);
+    }
+}
+
+#[test]
+fn test_decode_crop_clamped() {
+    let data = get_sample_image();
+
+    let mut decoder = Decoder::default();
+    decoder.crop = Some(Rect {
+        x0: 1400,
+        y0: 930,
+        xsize: 64,
+        ysize: 64,
+    });
+
+    let result = decoder
+        .decode(&data)
+        .expect("Failed to decode the sample image");
+    let frame = &result.frames[0];
+
+    assert_eq!(frame.xsize, 4);
+    assert_eq!(frame.ysize, 6);
+    assert_eq!(frame.data.len(), 4 * 6 * 4);
+}
+
+#[test]
+fn test_decode_downsample() {
+    let data = get_sample_image();
+
+    let mut decoder = Decoder::default();
+    decoder.downsample = Some(8);
+
+    let result = decoder
+        .decode(&data)
+        .expect("Failed to decode the sample image");
+    let frame = &result.frames[0];
+
+    assert!(!result.is_partial());
+    assert_eq!(frame.xsize, 176);
+    assert_eq!(frame.ysize, 117);
+    assert_eq!(frame.data.len(), 176 * 117 * 4);
+}
+
+#[test]
+fn test_decode_downsample_unsupported() {
+    let data = get_sample_image();
+
+    for factor in [0, 257] {
+        let mut decoder = Decoder::default();
+        decoder.downsample = Some(factor);
+
+        let err = decoder.decode(&data).unwrap_err();
+        assert!(matches!(err, JxlDecodeError::UnsupportedValue(_)));
+    }
+}
+
+#[test]
+fn test_decode_downsample_early() {
+    let data = get_sample_image();
+
+    let mut decoder = Decoder::default();
+    decoder.downsample = Some(8);
+    let full = decoder
+        .decode(&data)
+        .expect("Failed to decode the sample image");
+
+    // The DC of the sample is complete within these bytes, which is enough for 1:8
+    decoder.allow_partial = true;
+    let result = decoder
+        .decode(&data[..40960])
+        .expect("Failed to decode the sample image");
+    let frame = &result.frames[0];
+
+    // Without stopping at the DC, the truncated input would leave the frame without data
+    assert_eq!(frame.xsize, 176);
+    assert_eq!(frame.ysize, 117);
+    assert_eq!(frame.data.len(), 176 * 117 * 4);
+
+    let diff: u64 = frame
+        .data
+        .iter()
+        .zip(&full.frames[0].data)
+        .map(|(a, b)| (*a as i32 - *b as i32).unsigned_abs() as u64)
+        .sum();
+    assert!(
+        diff / (frame.data.len() as u64) < 8,
+        "Too different from the full decode"
+    );
+}
+
+#[test]
+fn test_decode_downsample_animation() {
+    let data = get_sample_animation();
+
+    let full = decode_memory(&data).expect("Failed to decode the sample image");
+
+    let mut decoder = Decoder::default();
+    decoder.downsample = Some(2);
+
+    let result = decoder
+        .decode(&data)
+        .expect("Failed to decode the sample image");
+
+    assert_eq!(result.frames.len(), 25);
+    for frame in result.frames {
+        assert_eq!(frame.xsize, full.basic_info.xsize.div_ceil(2));
+        assert_eq!(frame.ysize, full.basic_info.ysize.div_ceil(2));
+        assert_eq!(frame.data.len(), (frame.xsize * frame.ysize * 4) as usize);
+    }
+}
+
+#[test]
+fn test_decode_image_out_callback() {
+    let data = get_sample_image();
+
+    let full = decode_memory(&data).expect("Failed to decode the sample image");
+    let stride = full.basic_info.xsize as usize * 4;
+
+    let pixels = Arc::new(Mutex::new(vec![0u8; full.frames[0].data.len()]));
+    let pixels_clone = pixels.clone();
+
+    let mut decoder = Decoder::default();
+    decoder.image_out_callback = Some(Arc::new(move |x, y, num_pixels, row: &[u8]| {
+        assert_eq!(row.len(), num_pixels * 4);
+        let start = y * stride + x * 4;
+        pixels_clone.lock().unwrap()[start..start + row.len()].copy_from_slice(row);
+    }));
+
+    let result = decoder
+        .decode(&data)
+        .expect("Failed to decode the sample image");
+
+    assert_eq!(result.frames.len(), 1);
+    assert_eq!(result.frames[0].data.len(), 0);
+    assert_eq!(*pixels.lock().unwrap(), full.frames[0].data);
+}
+
+#[test]
+fn test_decode_image_out_callback_panic() {
+    let data = get_sample_image();
+
+    let mut decoder = Decoder::default();
+    decoder.image_out_callback = Some(Arc::new(|_, _, _, _: &[u8]| panic!("Callback panicked")));
+
+    // The panic should reach the caller instead of unwinding through libjxl
+    let payload = std::panic::catch_unwind(AssertUnwindSafe(|| decoder.decode(&data)))
+        .expect_err("The panic should be resumed");
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"Callback panicked"));
+}
+
+#[test]
+fn test_decode_into() {
+    let data = get_sample_image();
+
+    let full = decode_memory(&data).expect("Failed to decode the sample image");
+    let (xsize, ysize) = (1404, 936);
+
+    // 1404 * 3 * 2 = 8424 bytes per row, padded to 8448
+    let stride = 8448 / 2;
+    let mut buffer = vec![0u16; stride * ysize];
+
+    let result = Decoder::default()
+        .decode_into(&data, &mut buffer, 3, 64)
+        .expect("Failed to decode the sample image");
+
+    assert_eq!(result.frames.len(), 1);
+    assert_eq!(result.frames[0].data.len(), 0);
+    for (x, y) in [(0, 0), (700, 400), (xsize - 1, ysize - 1)] {
+        let rgba = &full.frames[0].data[(y * xsize + x) * 4..];
+        let rgb = &buffer[y * stride + x * 3..];
+        for c in 0..3 {
+            let diff = rgb[c] as f32 / 257.0 - rgba[c] as f32;
+            assert!(diff.abs() <= 1.0, "Sample mismatch at ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn test_decode_into_too_small() {
+    let data = get_sample_image();
+
+    let mut buffer = vec![0u8; 1404 * 936 * 4 - 1];
+    let err = Decoder::default()
+        .decode_into(&data, &mut buffer, 4, 0)
+        .unwrap_err();
+    assert!(matches!(err, JxlDecodeError::BufferTooSmall));
+}
+
+#[test]
+fn test_decode_into_num_channels() {
+    let data = get_sample_image();
+
+    let mut buffer = vec![0u8; 1404 * 936 * 5];
+    for num_channels in [0, 5] {
+        let err = Decoder::default()
+            .decode_into(&data, &mut buffer, num_channels, 0)
+            .unwrap_err();
+        assert!(matches!(err, JxlDecodeError::UnsupportedValue(_)));
+    }
+}
+
 #[test]
 fn test_decode_file() {
     let file = get_sample_image_file();
@@ -181,7 +416,7 @@ fn test_decode_animation_step() {
     for i in 2..=25 {
         result
             .proceed(&mut buffer, false, true)
-            .expect(format!("Should be able to proceed: frame {}", i).as_str());
+            .unwrap_or_else(|_| panic!("Should be able to proceed: frame {}", i));
         assert_eq!(result.frames.len(), i);
         if i == 25 {
             assert!(!result.is_partial());
@@ -191,6 +426,40 @@ fn test_decode_animation_step() {
     }
 }
 
+#[test]
+fn test_decode_animation_layers() {
+    let data = get_sample_animation();
+
+    let mut decoder = Decoder::default();
+    decoder.coalescing = Some(false);
+
+    let result = decoder
+        .decode(&data)
+        .expect("Failed to decode the sample image");
+    let basic_info = &result.basic_info;
+
+    assert_eq!(result.frames.len(), 25);
+    for frame in result.frames {
+        assert!(frame.xsize <= basic_info.xsize);
+        assert!(frame.ysize <= basic_info.ysize);
+        assert_eq!(frame.data.len(), (frame.xsize * frame.ysize * 4) as usize);
+    }
+}
+
+#[test]
+fn test_decode_coalesced_frame_size() {
+    let data = get_sample_animation();
+
+    let result = decode_memory(&data).expect("Failed to decode the sample image");
+    let basic_info = &result.basic_info;
+
+    for frame in result.frames {
+        assert!(!frame.have_crop);
+        assert_eq!(frame.xsize, basic_info.xsize);
+        assert_eq!(frame.ysize, basic_info.ysize);
+    }
+}
+
 #[test]
 fn test_decode_partial() {
     let data = get_sample_image();
@@ -1,4 +1,16 @@
-use kagamijxl::{decode_memory, encode_memory, BitmapFrame, Encoder, JpegFrame, JxlEncodeError};
+#![cfg(feature = "encoder")]
+
+use kagamijxl::{
+    decode_memory, distance_from_quality, encode_memory, reconstruct_jpeg, BitmapFrame, BlendInfo,
+    ColorEncoding, Decoder, Encoder, FrameInfo, FrameSettings, JpegFrame, JxlEncodeError,
+    PixelFormat, PixelFrame, TargetSize,
+};
+use libjxl_sys::{
+    JxlColorEncodingSetToSRGB, JXL_BLEND_BLEND,
+    JXL_ENC_FRAME_SETTING_MODULAR_MA_TREE_LEARNING_PERCENT,
+    JXL_ENC_FRAME_SETTING_MODULAR_PREDICTOR, JXL_NATIVE_ENDIAN, JXL_PRIMARIES_P3, JXL_TYPE_FLOAT,
+    JXL_TYPE_UINT16, JXL_TYPE_UINT8,
+};
 use std::path::PathBuf;
 
 #[rustfmt::skip]
@@ -19,6 +31,14 @@ fn get_sample_jpeg() -> Vec<u8> {
     std::fs::read(sample_jpeg_path()).expect("Failed to read the sample image")
 }
 
+fn get_sample_pixels() -> (Vec<u8>, u32, u32) {
+    let path = PathBuf::from(MANIFEST_DIR).join("tests/resources/sample.jxl");
+    let data = std::fs::read(path).expect("Failed to read the sample image");
+    let mut result = decode_memory(&data).expect("Failed to decode the sample image");
+    let frame = result.frames.remove(0);
+    (frame.data, result.basic_info.xsize, result.basic_info.ysize)
+}
+
 #[test]
 fn test_encode_memory() {
     let encoded = encode_memory(&RGBA_DATA, 3, 3).expect("Failed to encode");
@@ -73,6 +93,179 @@ fn test_encode_lossless() {
     assert_eq!(result.frames[0].data[..], RGBA_DATA);
 }
 
+#[test]
+fn test_encode_frame_settings() {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.frame_settings = FrameSettings {
+        modular: Some(true),
+        responsive: Some(true),
+        group_order: Some(1),
+        palette_colors: Some(0),
+        raw: vec![(JXL_ENC_FRAME_SETTING_MODULAR_PREDICTOR, 5)],
+        raw_float: vec![(JXL_ENC_FRAME_SETTING_MODULAR_MA_TREE_LEARNING_PERCENT, 50.0)],
+        ..Default::default()
+    };
+
+    let encoded = encoder.encode(&RGBA_DATA).expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode again");
+    assert_eq!(result.frames[0].data[..], RGBA_DATA);
+}
+
+#[test]
+fn test_encode_photon_noise() {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.frame_settings.photon_noise = Some(3200.0);
+
+    let encoded = encoder.encode(&RGBA_DATA).expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode again");
+    assert_eq!(result.basic_info.xsize, 3);
+}
+
+#[test]
+fn test_encode_frame_settings_unsupported() {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.frame_settings.resampling = Some(3);
+
+    let err = encoder.encode(&RGBA_DATA).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+}
+
+#[test]
+fn test_distance_from_quality() {
+    assert_eq!(distance_from_quality(100.0), 0.0);
+    assert!((distance_from_quality(90.0) - 1.0).abs() < 1e-6);
+    assert!((distance_from_quality(30.0) - 6.4).abs() < 1e-5);
+    assert!((distance_from_quality(0.0) - 25.0).abs() < 1e-6);
+    assert!(distance_from_quality(50.0) < distance_from_quality(20.0));
+}
+
+#[test]
+fn test_encode_quality() {
+    let mut encoder = Encoder::default();
+    encoder.quality = Some(75.0);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+
+    let encoded = encoder.encode(&RGBA_DATA).expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode again");
+    assert_eq!(result.basic_info.xsize, 3);
+    assert_eq!(result.basic_info.ysize, 3);
+}
+
+#[test]
+fn test_encode_quality_lossless() {
+    let mut encoder = Encoder::default();
+    encoder.quality = Some(100.0);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+
+    let encoded = encoder.encode(&RGBA_DATA).expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode again");
+    assert_eq!(result.frames[0].data[..], RGBA_DATA);
+}
+
+#[test]
+fn test_encode_quality_conflict() {
+    let mut encoder = Encoder::default();
+    encoder.quality = Some(90.0);
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+
+    let err = encoder.encode(&RGBA_DATA).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+
+    encoder.lossless = None;
+    encoder.distance = Some(1.0);
+
+    let err = encoder.encode(&RGBA_DATA).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+
+    encoder.distance = None;
+    encoder.quality = Some(101.0);
+
+    let err = encoder.encode(&RGBA_DATA).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+}
+
+#[test]
+fn test_encode_to_size() {
+    let (data, xsize, ysize) = get_sample_pixels();
+    let mut encoder = Encoder::default();
+    encoder.effort = Some(3);
+    encoder.basic_info.xsize = xsize;
+    encoder.basic_info.ysize = ysize;
+
+    let target = 50_000;
+    let result = encoder
+        .encode_to_size(&data, TargetSize::Bytes(target), 0.1)
+        .expect("Failed to encode");
+    assert!(result.data.len() <= target);
+    assert!(result.distance > 0.0);
+
+    let decoded = decode_memory(&result.data).expect("Failed to decode again");
+    assert_eq!(decoded.basic_info.xsize, xsize);
+    assert_eq!(decoded.basic_info.ysize, ysize);
+}
+
+#[test]
+fn test_encode_to_size_bits_per_pixel() {
+    let (data, xsize, ysize) = get_sample_pixels();
+    let mut encoder = Encoder::default();
+    encoder.effort = Some(3);
+    encoder.basic_info.xsize = xsize;
+    encoder.basic_info.ysize = ysize;
+
+    let result = encoder
+        .encode_to_size(&data, TargetSize::BitsPerPixel(1.0), 0.1)
+        .expect("Failed to encode");
+    let bpp = result.data.len() as f32 * 8.0 / (xsize * ysize) as f32;
+    assert!(bpp <= 1.0);
+}
+
+#[test]
+fn test_encode_to_size_unreachable() {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+
+    let err = encoder
+        .encode_to_size(&RGBA_DATA, TargetSize::Bytes(1), 0.1)
+        .unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+
+    encoder.distance = Some(1.0);
+    let err = encoder
+        .encode_to_size(&RGBA_DATA, TargetSize::Bytes(1000), 0.1)
+        .unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+}
+
+#[test]
+fn test_encode_to_size_unsupported_tolerance() {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+
+    for tolerance in [0.0, -0.1, 1.0, f32::NAN] {
+        let err = encoder
+            .encode_to_size(&RGBA_DATA, TargetSize::Bytes(1000), tolerance)
+            .unwrap_err();
+        assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+    }
+}
+
 #[test]
 fn test_encode_frame() {
     let mut encoder = Encoder::default();
@@ -92,6 +285,256 @@ fn test_encode_frame() {
     assert_eq!(result.frames[0].data[..], RGBA_DATA);
 }
 
+#[test]
+fn test_encode_frames() {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.basic_info.have_animation = true as _;
+    encoder.basic_info.animation.tps_numerator = 10;
+    encoder.basic_info.animation.tps_denominator = 1;
+
+    let first = BitmapFrame { data: &RGBA_DATA };
+    let first_info = FrameInfo {
+        duration: 1,
+        ..Default::default()
+    };
+
+    let patch = [0x01, 0x02, 0x03, 0xff];
+    let second = BitmapFrame { data: &patch };
+    let second_info = FrameInfo {
+        duration: 1,
+        have_crop: true,
+        crop_x0: 1,
+        crop_y0: 1,
+        xsize: 1,
+        ysize: 1,
+        ..Default::default()
+    };
+
+    let encoded = encoder
+        .encode_frames(&[(&first, first_info), (&second, second_info)])
+        .expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode again");
+    assert_eq!(result.frames.len(), 2);
+    assert_eq!(result.frames[0].data[..], RGBA_DATA);
+
+    let mut expected = RGBA_DATA;
+    expected[16..20].copy_from_slice(&patch);
+    assert_eq!(result.frames[1].data[..], expected);
+
+    let mut decoder = Decoder::default();
+    decoder.coalescing = Some(false);
+    let result = decoder.decode(&encoded).expect("Failed to decode again");
+    assert_eq!(result.frames.len(), 2);
+    assert!(result.frames[1].have_crop);
+    assert_eq!(result.frames[1].crop_x0, 1);
+    assert_eq!(result.frames[1].crop_y0, 1);
+    assert_eq!(result.frames[1].data[..], patch);
+}
+
+#[test]
+fn test_encode_frames_blend_info() {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.basic_info.have_animation = true as _;
+    encoder.basic_info.animation.tps_numerator = 10;
+    encoder.basic_info.animation.tps_denominator = 1;
+
+    let first = BitmapFrame { data: &RGBA_DATA };
+    let first_info = FrameInfo {
+        duration: 1,
+        save_as_reference: 1,
+        ..Default::default()
+    };
+
+    let patch = [0x01, 0x02, 0x03, 0xff];
+    let second = BitmapFrame { data: &patch };
+    let second_info = FrameInfo {
+        duration: 1,
+        have_crop: true,
+        crop_x0: 1,
+        crop_y0: 1,
+        xsize: 1,
+        ysize: 1,
+        blend_info: BlendInfo {
+            blendmode: JXL_BLEND_BLEND,
+            source: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let encoded = encoder
+        .encode_frames(&[(&first, first_info), (&second, second_info)])
+        .expect("Failed to encode");
+
+    let mut decoder = Decoder::default();
+    decoder.coalescing = Some(false);
+    let result = decoder.decode(&encoded).expect("Failed to decode again");
+    assert_eq!(result.frames.len(), 2);
+    assert_eq!(result.frames[0].save_as_reference, 1);
+    assert_eq!(result.frames[1].blend_info.blendmode, JXL_BLEND_BLEND);
+    assert_eq!(result.frames[1].blend_info.source, 1);
+
+    // The opaque patch covers the saved first frame
+    let result = decode_memory(&encoded).expect("Failed to decode again");
+    let mut expected = RGBA_DATA;
+    expected[16..20].copy_from_slice(&patch);
+    assert_eq!(result.frames[1].data[..], expected);
+}
+
+#[test]
+fn test_encode_pixel_frame_u16_padded() {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.basic_info.bits_per_sample = 16;
+    encoder.basic_info.alpha_bits = 0;
+    encoder.basic_info.num_extra_channels = 0;
+
+    // 3 pixels * 3 channels * 2 bytes = 18 bytes per row, padded to 24
+    let samples: Vec<u16> = (0..27).map(|i| i * 2400).collect();
+    let mut data = Vec::new();
+    for row in samples.chunks(9) {
+        for sample in row {
+            data.extend_from_slice(&sample.to_ne_bytes());
+        }
+        data.extend_from_slice(&[0; 6]);
+    }
+
+    let frame = PixelFrame {
+        data: &data,
+        pixel_format: PixelFormat {
+            num_channels: 3,
+            data_type: JXL_TYPE_UINT16,
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 8,
+        },
+        planar: false,
+    };
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+
+    let mut decoded = vec![0u16; 27];
+    Decoder::default()
+        .decode_into(&encoded, &mut decoded, 3, 0)
+        .expect("Failed to decode again");
+    assert_eq!(decoded, samples);
+}
+
+#[test]
+fn test_encode_pixel_frame_planar_gray() {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.basic_info.num_color_channels = 1;
+
+    let gray = &RGBA_DATA[..9];
+    let alpha = &RGBA_DATA[9..18];
+    let frame = PixelFrame {
+        data: &[gray, alpha].concat(),
+        pixel_format: PixelFormat {
+            num_channels: 2,
+            data_type: JXL_TYPE_UINT8,
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 0,
+        },
+        planar: true,
+    };
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+
+    let mut decoded = vec![0u8; 18];
+    Decoder::default()
+        .decode_into(&encoded, &mut decoded, 2, 0)
+        .expect("Failed to decode again");
+    for i in 0..9 {
+        assert_eq!(decoded[i * 2], gray[i]);
+        assert_eq!(decoded[i * 2 + 1], alpha[i]);
+    }
+}
+
+#[test]
+fn test_encode_pixel_frame_size_overflow() {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+
+    for planar in [false, true] {
+        // The padded rows can't fit in memory, so the size must not wrap around
+        let frame = PixelFrame {
+            data: &RGBA_DATA,
+            pixel_format: PixelFormat {
+                num_channels: 4,
+                data_type: JXL_TYPE_UINT8,
+                endianness: JXL_NATIVE_ENDIAN,
+                align: usize::MAX,
+            },
+            planar,
+        };
+        let err = encoder.encode_frame(&frame).unwrap_err();
+        assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+    }
+}
+
+#[test]
+fn test_encode_pixel_frame_f32() {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.basic_info.bits_per_sample = 32;
+    encoder.basic_info.exponent_bits_per_sample = 8;
+    encoder.basic_info.alpha_bits = 32;
+    encoder.basic_info.alpha_exponent_bits = 8;
+
+    let samples: Vec<f32> = RGBA_DATA.iter().map(|&v| v as f32 / 255.0).collect();
+    let data: Vec<u8> = samples.iter().flat_map(|v| v.to_ne_bytes()).collect();
+
+    let frame = PixelFrame {
+        data: &data,
+        pixel_format: PixelFormat {
+            num_channels: 4,
+            data_type: JXL_TYPE_FLOAT,
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 0,
+        },
+        planar: false,
+    };
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+
+    let mut decoded = vec![0f32; 36];
+    Decoder::default()
+        .decode_into(&encoded, &mut decoded, 4, 0)
+        .expect("Failed to decode again");
+    assert_eq!(decoded, samples);
+}
+
+#[test]
+fn test_encode_pixel_frame_too_small() {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+
+    let frame = PixelFrame {
+        data: &RGBA_DATA[..35],
+        pixel_format: PixelFormat {
+            num_channels: 4,
+            data_type: JXL_TYPE_UINT8,
+            endianness: JXL_NATIVE_ENDIAN,
+            align: 0,
+        },
+        planar: false,
+    };
+    let err = encoder.encode_frame(&frame).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::BufferTooSmall));
+}
+
 #[test]
 fn test_encode_jpeg_frame() {
     let mut encoder = Encoder::default();
@@ -114,6 +557,146 @@ fn test_encode_jpeg_frame() {
     assert_eq!(result.frames[0].data[0], 57);
 }
 
+fn jpeg_encoder() -> Encoder {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 800;
+    encoder.basic_info.ysize = 533;
+    encoder.basic_info.alpha_bits = 0;
+    encoder.basic_info.num_extra_channels = 0;
+    encoder
+}
+
+#[test]
+fn test_encode_jpeg_frame_verify() {
+    let mut encoder = jpeg_encoder();
+    encoder.jpeg.effort = Some(3);
+    encoder.jpeg.verify = true;
+
+    let jpeg = get_sample_jpeg();
+    let frame = JpegFrame { data: &jpeg[..] };
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+
+    let reconstructed = reconstruct_jpeg(&encoded).expect("Failed to reconstruct");
+    assert_eq!(reconstructed, Some(jpeg));
+}
+
+#[test]
+fn test_encode_jpeg_frame_without_reconstruction() {
+    let mut encoder = jpeg_encoder();
+    encoder.jpeg.store_reconstruction = Some(false);
+
+    let jpeg = get_sample_jpeg();
+    let frame = JpegFrame { data: &jpeg[..] };
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+
+    let reconstructed = reconstruct_jpeg(&encoded).expect("Failed to reconstruct");
+    assert_eq!(reconstructed, None);
+
+    encoder.jpeg.verify = true;
+    let err = encoder.encode_frame(&frame).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+}
+
+#[test]
+fn test_encode_jpeg_frame_lossy() {
+    let jpeg = get_sample_jpeg();
+    let frame = JpegFrame { data: &jpeg[..] };
+    let transcoded = jpeg_encoder()
+        .encode_frame(&frame)
+        .expect("Failed to encode");
+
+    let mut encoder = jpeg_encoder();
+    encoder.distance = Some(4.0);
+    encoder.jpeg.lossy = true;
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+    assert!(encoded.len() < transcoded.len());
+
+    let result = decode_memory(&encoded).expect("Failed to decode again");
+    assert_eq!(result.basic_info.xsize, 800);
+    assert_eq!(result.basic_info.ysize, 533);
+
+    encoder.jpeg.verify = true;
+    let err = encoder.encode_frame(&frame).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+}
+
+/** Gets a Display P3 ICC profile as generated by libjxl */
+fn get_p3_profile() -> Vec<u8> {
+    let mut color_encoding = ColorEncoding::default();
+    unsafe { JxlColorEncodingSetToSRGB(&mut color_encoding, 0) };
+    color_encoding.primaries = JXL_PRIMARIES_P3;
+
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 3;
+    encoder.color_encoding = Some(color_encoding);
+    let encoded = encoder.encode(&RGBA_DATA).expect("Failed to encode");
+
+    let decoder = Decoder {
+        need_color_profile: true,
+        no_full_frame: true,
+        ..Default::default()
+    };
+    let result = decoder.decode(&encoded).expect("Failed to decode");
+    assert!(!result.color_profile.is_empty());
+    result.color_profile
+}
+
+/** Inserts the ICC profile as an APP2 segment right after the JFIF APP0 segment */
+fn insert_icc_profile(jpeg: &[u8], profile: &[u8]) -> Vec<u8> {
+    let app0_end = 4 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+    let length = (2 + 12 + 2 + profile.len()) as u16;
+
+    let mut result = jpeg[..app0_end].to_vec();
+    result.extend_from_slice(&[0xff, 0xe2]);
+    result.extend_from_slice(&length.to_be_bytes());
+    result.extend_from_slice(b"ICC_PROFILE\0");
+    result.extend_from_slice(&[1, 1]);
+    result.extend_from_slice(profile);
+    result.extend_from_slice(&jpeg[app0_end..]);
+    result
+}
+
+#[test]
+fn test_encode_jpeg_frame_lossy_color_profile() {
+    let profile = get_p3_profile();
+    let jpeg = insert_icc_profile(&get_sample_jpeg(), &profile);
+    let frame = JpegFrame { data: &jpeg[..] };
+
+    let mut encoder = jpeg_encoder();
+    encoder.distance = Some(4.0);
+    encoder.jpeg.lossy = true;
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+
+    let decoder = Decoder {
+        need_color_profile: true,
+        ..Default::default()
+    };
+    let result = decoder.decode(&encoded).expect("Failed to decode again");
+    assert_eq!(result.color_profile, profile);
+
+    // Without the embedded profile the output stays sRGB
+    let jpeg = get_sample_jpeg();
+    let frame = JpegFrame { data: &jpeg[..] };
+    let encoded = encoder.encode_frame(&frame).expect("Failed to encode");
+    let result = decoder.decode(&encoded).expect("Failed to decode again");
+    assert_ne!(result.color_profile, profile);
+}
+
+#[test]
+fn test_encode_jpeg_frame_invalid() {
+    let frame = JpegFrame {
+        data: &[0xff, 0xd8, 0xff, 0x00, 0x01, 0x02],
+    };
+    let err = jpeg_encoder().encode_frame(&frame).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+
+    let mut encoder = jpeg_encoder();
+    encoder.jpeg.lossy = true;
+    let err = encoder.encode_frame(&frame).unwrap_err();
+    assert!(matches!(err, JxlEncodeError::UnsupportedValue(_)));
+}
+
 #[test]
 fn test_encode_unsupported_values() {
     let mut encoder = Encoder::default();
@@ -0,0 +1,113 @@
+#![cfg(all(feature = "gif", feature = "encoder"))]
+
+use gif::{DisposalMethod, Repeat};
+use kagamijxl::{decode_memory, Encoder};
+use std::borrow::Cow;
+
+const PALETTE: [u8; 9] = [255, 0, 0, 0, 0, 255, 0, 0, 0];
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+/** Left, top, width, height, palette indices and disposal of a GIF frame */
+type GifFrame<'a> = (u16, u16, u16, u16, &'a [u8], DisposalMethod);
+
+/** Writes a 2x2 GIF with the delay for every frame, where index 0 is red, 1 is blue and 2 is transparent */
+fn write_gif(frames: &[GifFrame], repeat: Repeat, delay: u16) -> Vec<u8> {
+    let mut file = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut file, 2, 2, &PALETTE).expect("Failed to write");
+        encoder.set_repeat(repeat).expect("Failed to set repeat");
+        for (left, top, width, height, indices, dispose) in frames {
+            let frame = gif::Frame {
+                left: *left,
+                top: *top,
+                width: *width,
+                height: *height,
+                buffer: Cow::Borrowed(indices),
+                transparent: Some(2),
+                dispose: *dispose,
+                delay,
+                ..Default::default()
+            };
+            encoder.write_frame(&frame).expect("Failed to write frame");
+        }
+    }
+    file
+}
+
+#[test]
+fn test_gif_encode_animation() {
+    let file = write_gif(
+        &[
+            (0, 0, 2, 2, &[0, 0, 0, 2], DisposalMethod::Keep),
+            (1, 1, 1, 1, &[1], DisposalMethod::Background),
+            (0, 0, 1, 1, &[1], DisposalMethod::Previous),
+            (1, 0, 1, 1, &[2], DisposalMethod::Keep),
+        ],
+        Repeat::Finite(2),
+        7,
+    );
+    let encoded = Encoder::default()
+        .encode_gif(&file[..])
+        .expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode");
+    let animation = result.basic_info.animation;
+    assert_eq!(
+        (animation.tps_numerator, animation.tps_denominator),
+        (100, 1)
+    );
+    assert_eq!(animation.num_loops, 3);
+
+    let expected = [
+        [RED, RED, RED, CLEAR].concat(),
+        [RED, RED, RED, BLUE].concat(),
+        [BLUE, RED, RED, CLEAR].concat(),
+        [RED, RED, RED, CLEAR].concat(),
+    ];
+    assert_eq!(result.frames.len(), expected.len());
+    for (frame, expected) in result.frames.iter().zip(&expected) {
+        assert_eq!(frame.duration, 7);
+        assert_eq!(&frame.data, expected);
+    }
+}
+
+#[test]
+fn test_gif_encode_infinite() {
+    let file = write_gif(
+        &[(0, 0, 2, 2, &[0, 1, 1, 0], DisposalMethod::Any)],
+        Repeat::Infinite,
+        7,
+    );
+    let encoded = Encoder::default()
+        .encode_gif(&file[..])
+        .expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode");
+    assert_eq!(result.basic_info.animation.num_loops, 0);
+    assert_eq!(result.frames[0].data, [RED, BLUE, BLUE, RED].concat());
+}
+
+#[test]
+fn test_gif_encode_zero_delay() {
+    let file = write_gif(
+        &[
+            (0, 0, 2, 2, &[0, 0, 0, 0], DisposalMethod::Keep),
+            (0, 0, 2, 2, &[1, 1, 1, 1], DisposalMethod::Keep),
+        ],
+        Repeat::Infinite,
+        0,
+    );
+    let encoded = Encoder::default()
+        .encode_gif(&file[..])
+        .expect("Failed to encode");
+
+    // Each frame stays a frame of its own instead of a layer of the next one
+    let result = decode_memory(&encoded).expect("Failed to decode");
+    assert_eq!(result.frames.len(), 2);
+    for frame in &result.frames {
+        assert_eq!(frame.duration, 10);
+    }
+    assert_eq!(result.frames[0].data, [RED; 4].concat());
+}
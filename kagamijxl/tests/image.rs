@@ -0,0 +1,95 @@
+#![cfg(all(feature = "image", feature = "encoder"))]
+
+use image::{
+    AnimationDecoder, ColorType, DynamicImage, ImageBuffer, ImageDecoder, ImageEncoder, Luma, Rgb,
+    Rgba,
+};
+use kagamijxl::{Encoder, JxlImageDecoder, JxlImageEncoder};
+use std::{fs::File, path::PathBuf};
+
+fn sample_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/resources")
+        .join(name)
+}
+
+fn encode_lossless(image: &DynamicImage) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+
+    let mut encoded = Vec::new();
+    JxlImageEncoder::with_encoder(&mut encoded, encoder)
+        .write_image(
+            image.as_bytes(),
+            image.width(),
+            image.height(),
+            image.color(),
+        )
+        .expect("Failed to encode");
+    encoded
+}
+
+#[test]
+fn test_image_decode() {
+    let file = File::open(sample_path("sample.jxl")).expect("Failed to open the sample image");
+    let decoder = JxlImageDecoder::new(file).expect("Failed to read the header");
+    assert_eq!(decoder.dimensions(), (1404, 936));
+    let color_type = decoder.color_type();
+
+    let image = DynamicImage::from_decoder(decoder).expect("Failed to decode");
+    assert_eq!((image.width(), image.height()), (1404, 936));
+    assert_eq!(image.color(), color_type);
+}
+
+#[test]
+fn test_image_roundtrip_gray() {
+    let image = DynamicImage::ImageLuma8(ImageBuffer::from_fn(16, 8, |x, y| {
+        Luma([(x * 16 + y) as u8])
+    }));
+    let encoded = encode_lossless(&image);
+
+    let decoder = JxlImageDecoder::new(&encoded[..]).expect("Failed to read the header");
+    assert_eq!(decoder.color_type(), ColorType::L8);
+    let decoded = DynamicImage::from_decoder(decoder).expect("Failed to decode");
+    assert_eq!(decoded.as_bytes(), image.as_bytes());
+}
+
+#[test]
+fn test_image_roundtrip_rgba16() {
+    let image = DynamicImage::ImageRgba16(ImageBuffer::from_fn(8, 8, |x, y| {
+        Rgba([(x * 4000) as u16, (y * 4000) as u16, 1234, 65535])
+    }));
+    let encoded = encode_lossless(&image);
+
+    let decoder = JxlImageDecoder::new(&encoded[..]).expect("Failed to read the header");
+    assert_eq!(decoder.color_type(), ColorType::Rgba16);
+    let decoded = DynamicImage::from_decoder(decoder).expect("Failed to decode");
+    assert_eq!(decoded.as_bytes(), image.as_bytes());
+}
+
+#[test]
+fn test_image_roundtrip_rgb32f() {
+    let image = DynamicImage::ImageRgb32F(ImageBuffer::from_fn(4, 4, |x, y| {
+        Rgb([x as f32 / 4.0, y as f32 / 4.0, 0.5])
+    }));
+    let encoded = encode_lossless(&image);
+
+    let decoder = JxlImageDecoder::new(&encoded[..]).expect("Failed to read the header");
+    assert_eq!(decoder.color_type(), ColorType::Rgb32F);
+    let decoded = DynamicImage::from_decoder(decoder).expect("Failed to decode");
+    assert_eq!(decoded.as_bytes(), image.as_bytes());
+}
+
+#[test]
+fn test_image_animation() {
+    let file = File::open(sample_path("spinfox.jxl")).expect("Failed to open the sample image");
+    let decoder = JxlImageDecoder::new(file).expect("Failed to read the header");
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .expect("Failed to decode");
+
+    assert_eq!(frames.len(), 25);
+    let (numerator, _) = frames[0].delay().numer_denom_ms();
+    assert!(numerator > 0);
+}
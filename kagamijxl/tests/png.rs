@@ -0,0 +1,150 @@
+#![cfg(all(feature = "png", feature = "encoder"))]
+
+use kagamijxl::{decode_memory, Decoder, Encoder};
+use std::path::PathBuf;
+
+fn write_png(
+    xsize: u32,
+    ysize: u32,
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    setup: impl FnOnce(&mut png::Encoder<&mut Vec<u8>>),
+    data: &[u8],
+) -> Vec<u8> {
+    let mut file = Vec::new();
+    let mut encoder = png::Encoder::new(&mut file, xsize, ysize);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+    setup(&mut encoder);
+    let mut writer = encoder.write_header().expect("Failed to write header");
+    writer.write_image_data(data).expect("Failed to write data");
+    writer.finish().expect("Failed to finish");
+    file
+}
+
+fn lossless() -> Encoder {
+    Encoder {
+        lossless: Some(true),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_png_encode_gray_16bit() {
+    let data = [0x00, 0x10, 0x80, 0x00, 0xff, 0xff];
+    let file = write_png(
+        3,
+        1,
+        png::ColorType::Grayscale,
+        png::BitDepth::Sixteen,
+        |_| {},
+        &data,
+    );
+    let encoded = lossless().encode_png(&file[..]).expect("Failed to encode");
+
+    let mut pixels = vec![0u16; 3];
+    let result = Decoder::default()
+        .decode_into(&encoded, &mut pixels, 1, 0)
+        .expect("Failed to decode");
+    assert_eq!(result.basic_info.num_color_channels, 1);
+    assert_eq!(result.basic_info.bits_per_sample, 16);
+    assert_eq!(pixels, [0x0010, 0x8000, 0xffff]);
+}
+
+#[test]
+fn test_png_encode_palette() {
+    let file = write_png(
+        2,
+        1,
+        png::ColorType::Indexed,
+        png::BitDepth::Eight,
+        |encoder| {
+            encoder.set_palette(vec![255, 0, 0, 0, 0, 255]);
+            encoder.set_trns(vec![255, 0]);
+        },
+        &[0, 1],
+    );
+    let encoded = lossless().encode_png(&file[..]).expect("Failed to encode");
+
+    let result = decode_memory(&encoded).expect("Failed to decode");
+    assert_eq!(result.frames[0].data, [255, 0, 0, 255, 0, 0, 255, 0]);
+}
+
+#[test]
+fn test_png_encode_exif() {
+    let exif = b"MM\0\x2a\0\0\0\x08\0\0".to_vec();
+    let mut info = png::Info::with_size(1, 1);
+    info.color_type = png::ColorType::Rgb;
+    info.bit_depth = png::BitDepth::Eight;
+    info.exif_metadata = Some(exif.clone().into());
+
+    let mut file = Vec::new();
+    let encoder = png::Encoder::with_info(&mut file, info).expect("Failed to create encoder");
+    let mut writer = encoder.write_header().expect("Failed to write header");
+    writer
+        .write_image_data(&[1, 2, 3])
+        .expect("Failed to write data");
+    writer.finish().expect("Failed to finish");
+
+    let encoded = lossless().encode_png(&file[..]).expect("Failed to encode");
+    // The Exif box starts with a zero offset to the TIFF header
+    assert!(encoded
+        .windows(4 + exif.len())
+        .any(|window| window[..4] == [0, 0, 0, 0] && window[4..] == exif[..]));
+}
+
+#[test]
+fn test_png_apng_roundtrip() {
+    let sample_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/spinfox.jxl");
+    let data = std::fs::read(sample_path).expect("Failed to read the sample image");
+    let decoded = decode_memory(&data).expect("Failed to decode the sample image");
+
+    let mut file = Vec::new();
+    decoded.write_png(&mut file).expect("Failed to write PNG");
+
+    let reader = png::Decoder::new(&file[..])
+        .read_info()
+        .expect("Failed to read PNG");
+    let animation = reader.info().animation_control.expect("Should be APNG");
+    assert_eq!(animation.num_frames as usize, decoded.frames.len());
+    assert_eq!(animation.num_plays, decoded.basic_info.animation.num_loops);
+
+    let reencoded = lossless().encode_png(&file[..]).expect("Failed to encode");
+    let result = decode_memory(&reencoded).expect("Failed to decode");
+    assert_eq!(result.frames.len(), decoded.frames.len());
+    assert_eq!(result.frames[1].data, decoded.frames[1].data);
+
+    let animation = decoded.basic_info.animation;
+    let to_ms = |duration: u32| {
+        duration as u64 * 1000 * animation.tps_denominator as u64 / animation.tps_numerator as u64
+    };
+    assert_eq!(
+        result.frames[1].duration as u64,
+        to_ms(decoded.frames[1].duration)
+    );
+}
+
+#[test]
+fn test_png_write_color_profile() {
+    let sample_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/sample.jxl");
+    let data = std::fs::read(sample_path).expect("Failed to read the sample image");
+    let decoder = Decoder {
+        need_color_profile: true,
+        ..Default::default()
+    };
+    let decoded = decoder.decode(&data).expect("Failed to decode");
+    assert!(!decoded.color_profile.is_empty());
+
+    let mut file = Vec::new();
+    decoded.write_png(&mut file).expect("Failed to write PNG");
+
+    let reader = png::Decoder::new(&file[..])
+        .read_info()
+        .expect("Failed to read PNG");
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (1404, 936));
+    assert_eq!(
+        info.icc_profile.as_deref(),
+        Some(&decoded.color_profile[..])
+    );
+}
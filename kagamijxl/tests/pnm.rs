@@ -0,0 +1,77 @@
+use kagamijxl::pnm::{self, PnmImage};
+#[cfg(feature = "encoder")]
+use kagamijxl::{pnm::SampleType, Decoder, Encoder};
+
+#[cfg(feature = "encoder")]
+fn encode_lossless(image: &PnmImage) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = image.xsize;
+    encoder.basic_info.ysize = image.ysize;
+    encoder.basic_info.alpha_bits = 0;
+    encoder.basic_info.num_extra_channels = 0;
+    encoder.basic_info.bits_per_sample = image.sample_type.size() as u32 * 8;
+
+    encoder
+        .encode_frame(&image.as_frame())
+        .expect("Failed to encode")
+}
+
+#[test]
+#[cfg(feature = "encoder")]
+fn test_pnm_encode_ppm() {
+    let mut file = b"P6\n4 2\n255\n".to_vec();
+    file.extend((0..24).map(|value| value * 10));
+    let image = pnm::read(&file[..]).expect("Failed to read");
+    let encoded = encode_lossless(&image);
+
+    let mut pixels = vec![0u8; 24];
+    Decoder::default()
+        .decode_into(&encoded, &mut pixels, 3, 0)
+        .expect("Failed to decode");
+    assert_eq!(pixels, image.data);
+}
+
+#[test]
+#[cfg(feature = "encoder")]
+fn test_pnm_encode_pgm_16bit() {
+    let mut file = b"P5\n3 1\n65535\n".to_vec();
+    file.extend_from_slice(&[0x00, 0x10, 0x80, 0x00, 0xff, 0xff]);
+    let image = pnm::read(&file[..]).expect("Failed to read");
+    assert_eq!(image.sample_type, SampleType::U16);
+
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.basic_info.xsize = 3;
+    encoder.basic_info.ysize = 1;
+    encoder.basic_info.num_color_channels = 1;
+    encoder.basic_info.alpha_bits = 0;
+    encoder.basic_info.num_extra_channels = 0;
+    encoder.basic_info.bits_per_sample = 16;
+    let encoded = encoder
+        .encode_frame(&image.as_frame())
+        .expect("Failed to encode");
+
+    let mut pixels = vec![0u16; 3];
+    Decoder::default()
+        .decode_into(&encoded, &mut pixels, 1, 0)
+        .expect("Failed to decode");
+    assert_eq!(pixels, [0x0010, 0x8000, 0xffff]);
+}
+
+#[test]
+fn test_pnm_write_decoded_frame() {
+    let path =
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/sample.jxl");
+    let data = std::fs::read(path).expect("Failed to read the sample image");
+    let mut result = kagamijxl::decode_memory(&data).expect("Failed to decode");
+    let frame = result.frames.remove(0);
+
+    let image = PnmImage::from_rgba8(frame.xsize, frame.ysize, frame.data);
+    let mut file = Vec::new();
+    pnm::write(&image, &mut file).expect("Failed to write");
+    assert!(file.starts_with(b"P7\nWIDTH 1404\nHEIGHT 936\nDEPTH 4\n"));
+
+    let read = pnm::read(&file[..]).expect("Failed to read");
+    assert_eq!(read.data, image.data);
+}
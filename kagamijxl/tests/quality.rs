@@ -0,0 +1,71 @@
+#![cfg(feature = "encoder")]
+
+use kagamijxl::{decode_memory, quality, Encoder};
+
+fn get_sample_pixels() -> (Vec<u8>, u32, u32) {
+    let path =
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/sample.jxl");
+    let data = std::fs::read(path).expect("Failed to read the sample image");
+    let mut result = decode_memory(&data).expect("Failed to decode the sample image");
+    let frame = result.frames.remove(0);
+    (frame.data, result.basic_info.xsize, result.basic_info.ysize)
+}
+
+#[test]
+fn test_quality_lossless() {
+    let (data, xsize, ysize) = get_sample_pixels();
+    let mut encoder = Encoder::default();
+    encoder.lossless = Some(true);
+    encoder.effort = Some(3);
+    encoder.basic_info.xsize = xsize;
+    encoder.basic_info.ysize = ysize;
+
+    let report = quality::evaluate(&encoder, &data).expect("Failed to evaluate");
+    assert_eq!(report.butteraugli_max, 0.0);
+    assert_eq!(report.psnr, f64::INFINITY);
+    assert!(report.bits_per_pixel > 0.0);
+}
+
+#[test]
+fn test_quality_distance() {
+    let (data, xsize, ysize) = get_sample_pixels();
+    let mut encoder = Encoder::default();
+    encoder.effort = Some(3);
+    encoder.basic_info.xsize = xsize;
+    encoder.basic_info.ysize = ysize;
+
+    encoder.distance = Some(1.0);
+    let fine = quality::evaluate(&encoder, &data).expect("Failed to evaluate");
+    encoder.distance = Some(6.0);
+    let coarse = quality::evaluate(&encoder, &data).expect("Failed to evaluate");
+
+    assert!(fine.size > coarse.size);
+    assert!(fine.bits_per_pixel > coarse.bits_per_pixel);
+    assert!(fine.butteraugli_pnorm < coarse.butteraugli_pnorm);
+    assert!(fine.butteraugli_pnorm <= fine.butteraugli_max);
+    assert!(fine.psnr > coarse.psnr);
+}
+
+#[test]
+fn test_quality_unsupported_input() {
+    let mut encoder = Encoder::default();
+    encoder.basic_info.xsize = 2;
+    encoder.basic_info.ysize = 1;
+
+    // 16-bit RGBA or 8-bit RGB pixels don't match the expected size
+    for size in [16, 6] {
+        let err = quality::evaluate(&encoder, &vec![0; size]).unwrap_err();
+        assert!(matches!(err, quality::QualityError::UnsupportedValue(_)));
+    }
+}
+
+#[test]
+fn test_quality_encode_error() {
+    let mut encoder = Encoder::default();
+    encoder.quality = Some(200.0);
+    encoder.basic_info.xsize = 1;
+    encoder.basic_info.ysize = 1;
+
+    let err = quality::evaluate(&encoder, &[0, 0, 0, 0]).unwrap_err();
+    assert!(matches!(err, quality::QualityError::Encode(_)));
+}
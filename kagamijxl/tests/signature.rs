@@ -1,14 +1,40 @@
-use kagamijxl::check_signature;
-use libjxl_sys::JXL_SIG_CODESTREAM;
+use kagamijxl::{check_signature, container, sniff, Signature};
 
-#[test]
-fn test_signature_check() {
+fn read_sample() -> Vec<u8> {
     // Resolve path manually or it will fail when running each test
     let sample_path =
         std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/sample.jxl");
 
-    let data = std::fs::read(sample_path).expect("Failed to read the sample image");
+    std::fs::read(sample_path).expect("Failed to read the sample image")
+}
+
+#[test]
+fn test_signature_check() {
+    let data = read_sample();
     let result = check_signature(&data);
 
-    assert_eq!(result, JXL_SIG_CODESTREAM);
+    assert_eq!(result, Signature::Codestream);
+}
+
+#[test]
+fn test_signature_check_short() {
+    assert_eq!(check_signature(&[]), Signature::NotEnoughBytes);
+    assert_eq!(check_signature(&[0xff]), Signature::NotEnoughBytes);
+    assert_eq!(check_signature(b"GIF89a"), Signature::Invalid);
+}
+
+#[test]
+fn test_signature_sniff() {
+    let data = read_sample();
+    let mut reader = &data[..];
+    assert_eq!(sniff(&mut reader).unwrap(), Signature::Codestream);
+    assert_eq!(reader.len(), data.len() - 2);
+
+    let wrapped = container::wrap_codestream(&data);
+    let mut reader = &wrapped[..];
+    assert_eq!(sniff(&mut reader).unwrap(), Signature::Container);
+    assert_eq!(reader.len(), wrapped.len() - 12);
+
+    assert_eq!(sniff(&wrapped[..5]).unwrap(), Signature::NotEnoughBytes);
+    assert_eq!(sniff(&b"\x89PNG\r\n"[..]).unwrap(), Signature::Invalid);
 }
@@ -0,0 +1,16 @@
+use kagamijxl::{decoder_version, Version, MIN_VERSION};
+
+#[test]
+fn test_version_parse() {
+    let version = Version::from_u32(1_002_003);
+    assert_eq!(version, Version::new(1, 2, 3));
+    assert_eq!(version.to_string(), "1.2.3");
+    assert!(Version::new(0, 10, 0) > Version::new(0, 9, 9));
+}
+
+#[test]
+fn test_version_linked() {
+    assert!(decoder_version() >= MIN_VERSION);
+    #[cfg(feature = "encoder")]
+    assert_eq!(kagamijxl::encoder_version(), decoder_version());
+}
@@ -1,10 +1,59 @@
 use cmake::Config;
 use std::env;
+use std::fs;
 use std::path::Path;
 
+/** Copies the files in `from` whose names pass `filter` to `to` */
+fn copy_files(from: &Path, to: &Path, filter: impl Fn(&str) -> bool) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        if path.is_file() && filter(&name) {
+            fs::copy(&path, to.join(&*name)).unwrap();
+        }
+    }
+}
+
+/**
+ * Builds only the decoder and thread pool libraries, as the install target depends on the
+ * encoder, and copies them and the headers to where the install target would.
+ */
+fn build_decoder(config: &mut Config, path: &Path) {
+    // sjpeg only encodes JPEG files for the tools
+    config.define("JPEGXL_ENABLE_SJPEG", "OFF");
+
+    let targets = if cfg!(feature = "dynamic") {
+        ["jxl_dec", "jxl_threads"]
+    } else {
+        ["jxl_dec-static", "jxl_threads-static"]
+    };
+    let mut dst = None;
+    for target in &targets {
+        dst = Some(config.build_target(target).build());
+    }
+    let dst = dst.unwrap();
+
+    // Shared libraries also come with their versioned names and import libraries
+    copy_files(&dst.join("build/lib"), &dst.join("lib"), |name| {
+        name.contains("jxl_dec") || name.contains("jxl_threads")
+    });
+    // version.h and the export headers are generated in the build directory
+    copy_files(
+        &path.join("lib/include/jxl"),
+        &dst.join("include/jxl"),
+        |_| true,
+    );
+    copy_files(
+        &dst.join("build/lib/include/jxl"),
+        &dst.join("include/jxl"),
+        |_| true,
+    );
+}
+
 pub fn build() {
     let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("submodules/libjxl");
-    let mut config = Config::new(path);
+    let mut config = Config::new(&path);
     config.define("JPEGXL_ENABLE_OPENEXR", "OFF");
     config.define("JPEGXL_ENABLE_BENCHMARK", "OFF");
 
@@ -18,10 +67,22 @@ pub fn build() {
             .generator("Ninja");
     }
 
+    if cfg!(feature = "dynamic") {
+        config
+            .define("JPEGXL_STATIC", "OFF")
+            .define("BUILD_SHARED_LIBS", "ON");
+    } else {
+        config.define("JPEGXL_STATIC", "ON");
+    }
+
     config
-        .define("JPEGXL_STATIC", "ON")
         .define("BUILD_TESTING", "OFF")
         .define("JPEGXL_ENABLE_EXAMPLES", "OFF")
-        .define("JPEGXL_ENABLE_TOOLS", "OFF")
-        .build();
+        .define("JPEGXL_ENABLE_TOOLS", "OFF");
+
+    if cfg!(feature = "encoder") {
+        config.build();
+    } else {
+        build_decoder(&mut config, &path);
+    }
 }
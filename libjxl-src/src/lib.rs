@@ -18,6 +18,19 @@ pub fn print_cargo_link_from(dst: &str) {
     println!("cargo:rustc-link-lib=dylib=msvcrtd");
 
     println!("cargo:rustc-link-search=native={}/lib", dst);
+
+    let jxl = if cfg!(feature = "encoder") {
+        "jxl"
+    } else {
+        "jxl_dec"
+    };
+    if cfg!(feature = "dynamic") {
+        // The shared libraries already link brotli, highway and the C++ standard library
+        println!("cargo:rustc-link-lib=dylib={}", jxl);
+        println!("cargo:rustc-link-lib=dylib=jxl_threads");
+        return;
+    }
+
     println!("cargo:rustc-link-search=native={}/build/third_party", dst);
     println!(
         "cargo:rustc-link-search=native={}/build/third_party/brotli",
@@ -29,14 +42,15 @@ pub fn print_cargo_link_from(dst: &str) {
     );
 
     if cfg!(windows) {
-        println!("cargo:rustc-link-lib=static=jxl-static");
+        println!("cargo:rustc-link-lib=static={}-static", jxl);
         println!("cargo:rustc-link-lib=static=jxl_threads-static");
     } else {
-        println!("cargo:rustc-link-lib=static=jxl");
+        println!("cargo:rustc-link-lib=static={}", jxl);
         println!("cargo:rustc-link-lib=static=jxl_threads");
     }
     println!("cargo:rustc-link-lib=static=brotlicommon-static");
     println!("cargo:rustc-link-lib=static=brotlidec-static");
+    #[cfg(feature = "encoder")]
     println!("cargo:rustc-link-lib=static=brotlienc-static");
     println!("cargo:rustc-link-lib=static=hwy");
 
@@ -1,29 +1,80 @@
-extern crate bindgen;
-
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-fn main() {
+#[cfg(not(any(feature = "vendored", feature = "system")))]
+compile_error!("Either the `vendored` or the `system` feature must be enabled");
+
+/** The oldest libjxl whose API matches these bindings */
+#[cfg(feature = "system")]
+const MIN_SYSTEM_VERSION: &str = "0.7";
+/** The first libjxl without `butteraugli.h` and the `JxlEncoderOptions*` functions */
+#[cfg(feature = "system")]
+const MAX_SYSTEM_VERSION: &str = "0.9";
+
+/** Finds libjxl and libjxl_threads with pkg-config and returns their include directories */
+#[cfg(feature = "system")]
+fn link_libjxl() -> Vec<PathBuf> {
+    let mut config = pkg_config::Config::new();
+    config.range_version(MIN_SYSTEM_VERSION..MAX_SYSTEM_VERSION);
+
+    let libjxl = config
+        .probe("libjxl")
+        .expect("Unable to find libjxl with pkg-config");
+    let libjxl_threads = config
+        .probe("libjxl_threads")
+        .expect("Unable to find libjxl_threads with pkg-config");
+
+    let mut include_paths = libjxl.include_paths;
+    include_paths.extend(libjxl_threads.include_paths);
+    include_paths
+}
+
+/** Builds the vendored libjxl and returns its include directory */
+#[cfg(not(feature = "system"))]
+fn link_libjxl() -> Vec<PathBuf> {
     libjxl_src::build();
 
     let out_dir = std::env::var("OUT_DIR").unwrap();
-
-    println!("cargo:rerun-if-changed=wrapper.h");
     libjxl_src::print_cargo_link_from(&out_dir);
+    // Lets dependent build scripts find the shared libraries via DEP_JXL_LIB
+    println!("cargo:lib={}/lib", out_dir);
 
-    let include_dir = format!("{}/include", out_dir);
-    println!("cargo:include={}", include_dir);
+    vec![PathBuf::from(format!("{}/include", out_dir))]
+}
 
+/** The pre-generated bindings for libjxl 0.7, which differ when the encoder is left out */
+fn shipped_bindings_path() -> PathBuf {
+    let name = if cfg!(feature = "encoder") {
+        "v0_7.rs"
+    } else {
+        "v0_7_decoder.rs"
+    };
+    PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("src/bindings")
+        .join(name)
+}
+
+/** Generates the bindings from the headers */
+#[cfg(feature = "bindgen")]
+fn write_bindings(include_dirs: &[PathBuf], out_path: &Path, _shipped_path: &Path) {
     // The bindgen::Builder is the main entry point
     // to bindgen, and lets you build up options for
     // the resulting bindings.
-    let bindings = bindgen::Builder::default()
+    let mut builder = bindgen::Builder::default();
+    if cfg!(not(feature = "encoder")) {
+        // Leaves out the encoder API, which libjxl_dec does not have
+        builder = builder.clang_arg("-DLIBJXL_SYS_DECODER_ONLY");
+    }
+    let bindings = builder
         // The input header we would like to generate
         // bindings for.
         .header("wrapper.h")
         // Tell where to find the jxl/ headers.
-        .clang_arg("-I")
-        .clang_arg(include_dir)
+        .clang_args(
+            include_dirs
+                .iter()
+                .map(|include_dir| format!("-I{}", include_dir.display())),
+        )
         // Reduce noise from system libs.
         .allowlist_function("Jxl.*")
         // #[derive(Default)] for struct initialization.
@@ -33,14 +84,50 @@ fn main() {
         .size_t_is_usize(true)
         // libjxl already adds appropriate prefixes
         .prepend_enum_name(false)
+        // The shipped bindings are used on every platform, so leave out the layout tests,
+        // which only hold for the platform they are generated on, and the doc comments
+        .generate_comments(false)
+        .layout_tests(false)
         // Finish the builder and generate the bindings.
         .generate()
         // Unwrap the Result and panic on failure.
         .expect("Unable to generate bindings");
 
     // Write the bindings to the $OUT_DIR/bindings.rs file.
-    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
     bindings
-        .write_to_file(out_path.join("bindings.rs"))
+        .write_to_file(out_path)
         .expect("Couldn't write bindings!");
 }
+
+/** Uses the shipped bindings so that neither bindgen nor libclang is needed */
+#[cfg(not(feature = "bindgen"))]
+fn write_bindings(_include_dirs: &[PathBuf], out_path: &Path, shipped_path: &Path) {
+    println!("cargo:rerun-if-changed={}", shipped_path.display());
+    std::fs::copy(shipped_path, out_path).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't read the shipped bindings at {}, enable the bindgen feature to generate them: {}",
+            shipped_path.display(),
+            err
+        )
+    });
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=wrapper.h");
+
+    // The system feature takes precedence so that it can be enabled without
+    // disabling the default features of every dependent crate.
+    let include_dirs = link_libjxl();
+    if let Some(include_dir) = include_dirs.first() {
+        println!("cargo:include={}", include_dir.display());
+    }
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bindings.rs");
+    let shipped_path = shipped_bindings_path();
+    // Lets tests compare the generated bindings with the shipped ones
+    println!(
+        "cargo:rustc-env=LIBJXL_SYS_SHIPPED_BINDINGS={}",
+        shipped_path.display()
+    );
+    write_bindings(&include_dirs, &out_path, &shipped_path);
+}
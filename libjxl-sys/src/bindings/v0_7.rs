@@ -0,0 +1,1065 @@
+/* automatically generated by rust-bindgen 0.60.1 */
+
+pub const JXL_TYPE_FLOAT: JxlDataType = 0;
+pub const JXL_TYPE_BOOLEAN: JxlDataType = 1;
+pub const JXL_TYPE_UINT8: JxlDataType = 2;
+pub const JXL_TYPE_UINT16: JxlDataType = 3;
+pub const JXL_TYPE_UINT32: JxlDataType = 4;
+pub const JXL_TYPE_FLOAT16: JxlDataType = 5;
+pub type JxlDataType = ::std::os::raw::c_uint;
+pub const JXL_NATIVE_ENDIAN: JxlEndianness = 0;
+pub const JXL_LITTLE_ENDIAN: JxlEndianness = 1;
+pub const JXL_BIG_ENDIAN: JxlEndianness = 2;
+pub type JxlEndianness = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlPixelFormat {
+    pub num_channels: u32,
+    pub data_type: JxlDataType,
+    pub endianness: JxlEndianness,
+    pub align: usize,
+}
+pub type JxlBoxType = [::std::os::raw::c_char; 4usize];
+pub const JXL_ORIENT_IDENTITY: JxlOrientation = 1;
+pub const JXL_ORIENT_FLIP_HORIZONTAL: JxlOrientation = 2;
+pub const JXL_ORIENT_ROTATE_180: JxlOrientation = 3;
+pub const JXL_ORIENT_FLIP_VERTICAL: JxlOrientation = 4;
+pub const JXL_ORIENT_TRANSPOSE: JxlOrientation = 5;
+pub const JXL_ORIENT_ROTATE_90_CW: JxlOrientation = 6;
+pub const JXL_ORIENT_ANTI_TRANSPOSE: JxlOrientation = 7;
+pub const JXL_ORIENT_ROTATE_90_CCW: JxlOrientation = 8;
+pub type JxlOrientation = ::std::os::raw::c_uint;
+pub const JXL_CHANNEL_ALPHA: JxlExtraChannelType = 0;
+pub const JXL_CHANNEL_DEPTH: JxlExtraChannelType = 1;
+pub const JXL_CHANNEL_SPOT_COLOR: JxlExtraChannelType = 2;
+pub const JXL_CHANNEL_SELECTION_MASK: JxlExtraChannelType = 3;
+pub const JXL_CHANNEL_BLACK: JxlExtraChannelType = 4;
+pub const JXL_CHANNEL_CFA: JxlExtraChannelType = 5;
+pub const JXL_CHANNEL_THERMAL: JxlExtraChannelType = 6;
+pub const JXL_CHANNEL_RESERVED0: JxlExtraChannelType = 7;
+pub const JXL_CHANNEL_RESERVED1: JxlExtraChannelType = 8;
+pub const JXL_CHANNEL_RESERVED2: JxlExtraChannelType = 9;
+pub const JXL_CHANNEL_RESERVED3: JxlExtraChannelType = 10;
+pub const JXL_CHANNEL_RESERVED4: JxlExtraChannelType = 11;
+pub const JXL_CHANNEL_RESERVED5: JxlExtraChannelType = 12;
+pub const JXL_CHANNEL_RESERVED6: JxlExtraChannelType = 13;
+pub const JXL_CHANNEL_RESERVED7: JxlExtraChannelType = 14;
+pub const JXL_CHANNEL_UNKNOWN: JxlExtraChannelType = 15;
+pub const JXL_CHANNEL_OPTIONAL: JxlExtraChannelType = 16;
+pub type JxlExtraChannelType = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlPreviewHeader {
+    pub xsize: u32,
+    pub ysize: u32,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlAnimationHeader {
+    pub tps_numerator: u32,
+    pub tps_denominator: u32,
+    pub num_loops: u32,
+    pub have_timecodes: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlBasicInfo {
+    pub have_container: ::std::os::raw::c_int,
+    pub xsize: u32,
+    pub ysize: u32,
+    pub bits_per_sample: u32,
+    pub exponent_bits_per_sample: u32,
+    pub intensity_target: f32,
+    pub min_nits: f32,
+    pub relative_to_max_display: ::std::os::raw::c_int,
+    pub linear_below: f32,
+    pub uses_original_profile: ::std::os::raw::c_int,
+    pub have_preview: ::std::os::raw::c_int,
+    pub have_animation: ::std::os::raw::c_int,
+    pub orientation: JxlOrientation,
+    pub num_color_channels: u32,
+    pub num_extra_channels: u32,
+    pub alpha_bits: u32,
+    pub alpha_exponent_bits: u32,
+    pub alpha_premultiplied: ::std::os::raw::c_int,
+    pub preview: JxlPreviewHeader,
+    pub animation: JxlAnimationHeader,
+    pub intrinsic_xsize: u32,
+    pub intrinsic_ysize: u32,
+    pub padding: [u8; 100usize],
+}
+impl Default for JxlBasicInfo {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlExtraChannelInfo {
+    pub type_: JxlExtraChannelType,
+    pub bits_per_sample: u32,
+    pub exponent_bits_per_sample: u32,
+    pub dim_shift: u32,
+    pub name_length: u32,
+    pub alpha_premultiplied: ::std::os::raw::c_int,
+    pub spot_color: [f32; 4usize],
+    pub cfa_channel: u32,
+}
+pub const JXL_BLEND_REPLACE: JxlBlendMode = 0;
+pub const JXL_BLEND_ADD: JxlBlendMode = 1;
+pub const JXL_BLEND_BLEND: JxlBlendMode = 2;
+pub const JXL_BLEND_MULADD: JxlBlendMode = 3;
+pub const JXL_BLEND_MUL: JxlBlendMode = 4;
+pub type JxlBlendMode = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlBlendInfo {
+    pub blendmode: JxlBlendMode,
+    pub source: u32,
+    pub alpha: u32,
+    pub clamp: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlLayerInfo {
+    pub have_crop: ::std::os::raw::c_int,
+    pub crop_x0: i32,
+    pub crop_y0: i32,
+    pub xsize: u32,
+    pub ysize: u32,
+    pub blend_info: JxlBlendInfo,
+    pub save_as_reference: u32,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlFrameHeader {
+    pub duration: u32,
+    pub timecode: u32,
+    pub name_length: u32,
+    pub is_last: ::std::os::raw::c_int,
+    pub layer_info: JxlLayerInfo,
+}
+pub const JXL_COLOR_SPACE_RGB: JxlColorSpace = 0;
+pub const JXL_COLOR_SPACE_GRAY: JxlColorSpace = 1;
+pub const JXL_COLOR_SPACE_XYB: JxlColorSpace = 2;
+pub const JXL_COLOR_SPACE_UNKNOWN: JxlColorSpace = 3;
+pub type JxlColorSpace = ::std::os::raw::c_uint;
+pub const JXL_WHITE_POINT_D65: JxlWhitePoint = 1;
+pub const JXL_WHITE_POINT_CUSTOM: JxlWhitePoint = 2;
+pub const JXL_WHITE_POINT_E: JxlWhitePoint = 10;
+pub const JXL_WHITE_POINT_DCI: JxlWhitePoint = 11;
+pub type JxlWhitePoint = ::std::os::raw::c_uint;
+pub const JXL_PRIMARIES_SRGB: JxlPrimaries = 1;
+pub const JXL_PRIMARIES_CUSTOM: JxlPrimaries = 2;
+pub const JXL_PRIMARIES_2100: JxlPrimaries = 9;
+pub const JXL_PRIMARIES_P3: JxlPrimaries = 11;
+pub type JxlPrimaries = ::std::os::raw::c_uint;
+pub const JXL_TRANSFER_FUNCTION_709: JxlTransferFunction = 1;
+pub const JXL_TRANSFER_FUNCTION_UNKNOWN: JxlTransferFunction = 2;
+pub const JXL_TRANSFER_FUNCTION_LINEAR: JxlTransferFunction = 8;
+pub const JXL_TRANSFER_FUNCTION_SRGB: JxlTransferFunction = 13;
+pub const JXL_TRANSFER_FUNCTION_PQ: JxlTransferFunction = 16;
+pub const JXL_TRANSFER_FUNCTION_DCI: JxlTransferFunction = 17;
+pub const JXL_TRANSFER_FUNCTION_HLG: JxlTransferFunction = 18;
+pub const JXL_TRANSFER_FUNCTION_GAMMA: JxlTransferFunction = 65535;
+pub type JxlTransferFunction = ::std::os::raw::c_uint;
+pub const JXL_RENDERING_INTENT_PERCEPTUAL: JxlRenderingIntent = 0;
+pub const JXL_RENDERING_INTENT_RELATIVE: JxlRenderingIntent = 1;
+pub const JXL_RENDERING_INTENT_SATURATION: JxlRenderingIntent = 2;
+pub const JXL_RENDERING_INTENT_ABSOLUTE: JxlRenderingIntent = 3;
+pub type JxlRenderingIntent = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JxlColorEncoding {
+    pub color_space: JxlColorSpace,
+    pub white_point: JxlWhitePoint,
+    pub white_point_xy: [f64; 2usize],
+    pub primaries: JxlPrimaries,
+    pub primaries_red_xy: [f64; 2usize],
+    pub primaries_green_xy: [f64; 2usize],
+    pub primaries_blue_xy: [f64; 2usize],
+    pub transfer_function: JxlTransferFunction,
+    pub gamma: f64,
+    pub rendering_intent: JxlRenderingIntent,
+}
+pub type jpegxl_alloc_func = ::std::option::Option<
+    unsafe extern "C" fn(
+        opaque: *mut ::std::os::raw::c_void,
+        size: usize,
+    ) -> *mut ::std::os::raw::c_void,
+>;
+pub type jpegxl_free_func = ::std::option::Option<
+    unsafe extern "C" fn(opaque: *mut ::std::os::raw::c_void, address: *mut ::std::os::raw::c_void),
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlMemoryManagerStruct {
+    pub opaque: *mut ::std::os::raw::c_void,
+    pub alloc: jpegxl_alloc_func,
+    pub free: jpegxl_free_func,
+}
+impl Default for JxlMemoryManagerStruct {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+pub type JxlMemoryManager = JxlMemoryManagerStruct;
+pub type JxlParallelRetCode = ::std::os::raw::c_int;
+pub type JxlParallelRunInit = ::std::option::Option<
+    unsafe extern "C" fn(
+        jpegxl_opaque: *mut ::std::os::raw::c_void,
+        num_threads: usize,
+    ) -> JxlParallelRetCode,
+>;
+pub type JxlParallelRunFunction = ::std::option::Option<
+    unsafe extern "C" fn(jpegxl_opaque: *mut ::std::os::raw::c_void, value: u32, thread_id: usize),
+>;
+pub type JxlParallelRunner = ::std::option::Option<
+    unsafe extern "C" fn(
+        runner_opaque: *mut ::std::os::raw::c_void,
+        jpegxl_opaque: *mut ::std::os::raw::c_void,
+        init: JxlParallelRunInit,
+        func: JxlParallelRunFunction,
+        start_range: u32,
+        end_range: u32,
+    ) -> JxlParallelRetCode,
+>;
+extern "C" {
+    pub fn JxlDecoderVersion() -> u32;
+}
+pub const JXL_SIG_NOT_ENOUGH_BYTES: JxlSignature = 0;
+pub const JXL_SIG_INVALID: JxlSignature = 1;
+pub const JXL_SIG_CODESTREAM: JxlSignature = 2;
+pub const JXL_SIG_CONTAINER: JxlSignature = 3;
+pub type JxlSignature = ::std::os::raw::c_uint;
+extern "C" {
+    pub fn JxlSignatureCheck(buf: *const u8, len: usize) -> JxlSignature;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlDecoderStruct {
+    _unused: [u8; 0],
+}
+pub type JxlDecoder = JxlDecoderStruct;
+extern "C" {
+    pub fn JxlDecoderCreate(memory_manager: *const JxlMemoryManager) -> *mut JxlDecoder;
+}
+extern "C" {
+    pub fn JxlDecoderReset(dec: *mut JxlDecoder);
+}
+extern "C" {
+    pub fn JxlDecoderDestroy(dec: *mut JxlDecoder);
+}
+pub const JXL_DEC_SUCCESS: JxlDecoderStatus = 0;
+pub const JXL_DEC_ERROR: JxlDecoderStatus = 1;
+pub const JXL_DEC_NEED_MORE_INPUT: JxlDecoderStatus = 2;
+pub const JXL_DEC_NEED_PREVIEW_OUT_BUFFER: JxlDecoderStatus = 3;
+pub const JXL_DEC_NEED_DC_OUT_BUFFER: JxlDecoderStatus = 4;
+pub const JXL_DEC_NEED_IMAGE_OUT_BUFFER: JxlDecoderStatus = 5;
+pub const JXL_DEC_JPEG_NEED_MORE_OUTPUT: JxlDecoderStatus = 6;
+pub const JXL_DEC_BOX_NEED_MORE_OUTPUT: JxlDecoderStatus = 7;
+pub const JXL_DEC_BASIC_INFO: JxlDecoderStatus = 64;
+pub const JXL_DEC_EXTENSIONS: JxlDecoderStatus = 128;
+pub const JXL_DEC_COLOR_ENCODING: JxlDecoderStatus = 256;
+pub const JXL_DEC_PREVIEW_IMAGE: JxlDecoderStatus = 512;
+pub const JXL_DEC_FRAME: JxlDecoderStatus = 1024;
+pub const JXL_DEC_DC_IMAGE: JxlDecoderStatus = 2048;
+pub const JXL_DEC_FULL_IMAGE: JxlDecoderStatus = 4096;
+pub const JXL_DEC_JPEG_RECONSTRUCTION: JxlDecoderStatus = 8192;
+pub const JXL_DEC_BOX: JxlDecoderStatus = 16384;
+pub const JXL_DEC_FRAME_PROGRESSION: JxlDecoderStatus = 32768;
+pub type JxlDecoderStatus = ::std::os::raw::c_uint;
+extern "C" {
+    pub fn JxlDecoderRewind(dec: *mut JxlDecoder);
+}
+extern "C" {
+    pub fn JxlDecoderSkipFrames(dec: *mut JxlDecoder, amount: usize);
+}
+extern "C" {
+    pub fn JxlDecoderSkipCurrentFrame(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderDefaultPixelFormat(
+        dec: *const JxlDecoder,
+        format: *mut JxlPixelFormat,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetParallelRunner(
+        dec: *mut JxlDecoder,
+        parallel_runner: JxlParallelRunner,
+        parallel_runner_opaque: *mut ::std::os::raw::c_void,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSizeHintBasicInfo(dec: *const JxlDecoder) -> usize;
+}
+extern "C" {
+    pub fn JxlDecoderSubscribeEvents(
+        dec: *mut JxlDecoder,
+        events_wanted: ::std::os::raw::c_int,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetKeepOrientation(
+        dec: *mut JxlDecoder,
+        skip_reorientation: ::std::os::raw::c_int,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetUnpremultiplyAlpha(
+        dec: *mut JxlDecoder,
+        unpremul_alpha: ::std::os::raw::c_int,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetRenderSpotcolors(
+        dec: *mut JxlDecoder,
+        render_spotcolors: ::std::os::raw::c_int,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetCoalescing(
+        dec: *mut JxlDecoder,
+        coalescing: ::std::os::raw::c_int,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderProcessInput(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetInput(
+        dec: *mut JxlDecoder,
+        data: *const u8,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderReleaseInput(dec: *mut JxlDecoder) -> usize;
+}
+extern "C" {
+    pub fn JxlDecoderCloseInput(dec: *mut JxlDecoder);
+}
+extern "C" {
+    pub fn JxlDecoderGetBasicInfo(
+        dec: *const JxlDecoder,
+        info: *mut JxlBasicInfo,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetExtraChannelInfo(
+        dec: *const JxlDecoder,
+        index: usize,
+        info: *mut JxlExtraChannelInfo,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetExtraChannelName(
+        dec: *const JxlDecoder,
+        index: usize,
+        name: *mut ::std::os::raw::c_char,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+pub const JXL_COLOR_PROFILE_TARGET_ORIGINAL: JxlColorProfileTarget = 0;
+pub const JXL_COLOR_PROFILE_TARGET_DATA: JxlColorProfileTarget = 1;
+pub type JxlColorProfileTarget = ::std::os::raw::c_uint;
+extern "C" {
+    pub fn JxlDecoderGetColorAsEncodedProfile(
+        dec: *const JxlDecoder,
+        format: *const JxlPixelFormat,
+        target: JxlColorProfileTarget,
+        color_encoding: *mut JxlColorEncoding,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetICCProfileSize(
+        dec: *const JxlDecoder,
+        format: *const JxlPixelFormat,
+        target: JxlColorProfileTarget,
+        size: *mut usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetColorAsICCProfile(
+        dec: *const JxlDecoder,
+        format: *const JxlPixelFormat,
+        target: JxlColorProfileTarget,
+        icc_profile: *mut u8,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetPreferredColorProfile(
+        dec: *mut JxlDecoder,
+        color_encoding: *const JxlColorEncoding,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetDesiredIntensityTarget(
+        dec: *mut JxlDecoder,
+        desired_intensity_target: f32,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderPreviewOutBufferSize(
+        dec: *const JxlDecoder,
+        format: *const JxlPixelFormat,
+        size: *mut usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetPreviewOutBuffer(
+        dec: *mut JxlDecoder,
+        format: *const JxlPixelFormat,
+        buffer: *mut ::std::os::raw::c_void,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetFrameHeader(
+        dec: *const JxlDecoder,
+        header: *mut JxlFrameHeader,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetFrameName(
+        dec: *const JxlDecoder,
+        name: *mut ::std::os::raw::c_char,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetExtraChannelBlendInfo(
+        dec: *const JxlDecoder,
+        index: usize,
+        blend_info: *mut JxlBlendInfo,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderDCOutBufferSize(
+        dec: *const JxlDecoder,
+        format: *const JxlPixelFormat,
+        size: *mut usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetDCOutBuffer(
+        dec: *mut JxlDecoder,
+        format: *const JxlPixelFormat,
+        buffer: *mut ::std::os::raw::c_void,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderImageOutBufferSize(
+        dec: *const JxlDecoder,
+        format: *const JxlPixelFormat,
+        size: *mut usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetImageOutBuffer(
+        dec: *mut JxlDecoder,
+        format: *const JxlPixelFormat,
+        buffer: *mut ::std::os::raw::c_void,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+pub type JxlImageOutCallback = ::std::option::Option<
+    unsafe extern "C" fn(
+        opaque: *mut ::std::os::raw::c_void,
+        x: usize,
+        y: usize,
+        num_pixels: usize,
+        pixels: *const ::std::os::raw::c_void,
+    ),
+>;
+pub type JxlImageOutInitCallback = ::std::option::Option<
+    unsafe extern "C" fn(
+        init_opaque: *mut ::std::os::raw::c_void,
+        num_threads: usize,
+        num_pixels_per_thread: usize,
+    ) -> *mut ::std::os::raw::c_void,
+>;
+pub type JxlImageOutRunCallback = ::std::option::Option<
+    unsafe extern "C" fn(
+        run_opaque: *mut ::std::os::raw::c_void,
+        thread_id: usize,
+        x: usize,
+        y: usize,
+        num_pixels: usize,
+        pixels: *const ::std::os::raw::c_void,
+    ),
+>;
+pub type JxlImageOutDestroyCallback =
+    ::std::option::Option<unsafe extern "C" fn(run_opaque: *mut ::std::os::raw::c_void)>;
+extern "C" {
+    pub fn JxlDecoderSetImageOutCallback(
+        dec: *mut JxlDecoder,
+        format: *const JxlPixelFormat,
+        callback: JxlImageOutCallback,
+        opaque: *mut ::std::os::raw::c_void,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetMultithreadedImageOutCallback(
+        dec: *mut JxlDecoder,
+        format: *const JxlPixelFormat,
+        init_callback: JxlImageOutInitCallback,
+        run_callback: JxlImageOutRunCallback,
+        destroy_callback: JxlImageOutDestroyCallback,
+        init_opaque: *mut ::std::os::raw::c_void,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderExtraChannelBufferSize(
+        dec: *const JxlDecoder,
+        format: *const JxlPixelFormat,
+        size: *mut usize,
+        index: u32,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetExtraChannelBuffer(
+        dec: *mut JxlDecoder,
+        format: *const JxlPixelFormat,
+        buffer: *mut ::std::os::raw::c_void,
+        size: usize,
+        index: u32,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderSetJPEGBuffer(
+        dec: *mut JxlDecoder,
+        data: *mut u8,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderReleaseJPEGBuffer(dec: *mut JxlDecoder) -> usize;
+}
+extern "C" {
+    pub fn JxlDecoderSetBoxBuffer(
+        dec: *mut JxlDecoder,
+        data: *mut u8,
+        size: usize,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderReleaseBoxBuffer(dec: *mut JxlDecoder) -> usize;
+}
+extern "C" {
+    pub fn JxlDecoderSetDecompressBoxes(
+        dec: *mut JxlDecoder,
+        decompress: ::std::os::raw::c_int,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetBoxType(
+        dec: *mut JxlDecoder,
+        type_: *mut ::std::os::raw::c_char,
+        decompressed: ::std::os::raw::c_int,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetBoxSizeRaw(dec: *const JxlDecoder, size: *mut u64) -> JxlDecoderStatus;
+}
+pub const kFrames: JxlProgressiveDetail = 0;
+pub const kDC: JxlProgressiveDetail = 1;
+pub const kLastPasses: JxlProgressiveDetail = 2;
+pub const kPasses: JxlProgressiveDetail = 3;
+pub const kDCProgressive: JxlProgressiveDetail = 4;
+pub const kDCGroups: JxlProgressiveDetail = 5;
+pub const kGroups: JxlProgressiveDetail = 6;
+pub type JxlProgressiveDetail = ::std::os::raw::c_uint;
+extern "C" {
+    pub fn JxlDecoderSetProgressiveDetail(
+        dec: *mut JxlDecoder,
+        detail: JxlProgressiveDetail,
+    ) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlDecoderGetIntendedDownsamplingRatio(dec: *mut JxlDecoder) -> usize;
+}
+extern "C" {
+    pub fn JxlDecoderFlushImage(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+}
+extern "C" {
+    pub fn JxlThreadParallelRunner(
+        runner_opaque: *mut ::std::os::raw::c_void,
+        jpegxl_opaque: *mut ::std::os::raw::c_void,
+        init: JxlParallelRunInit,
+        func: JxlParallelRunFunction,
+        start_range: u32,
+        end_range: u32,
+    ) -> JxlParallelRetCode;
+}
+extern "C" {
+    pub fn JxlThreadParallelRunnerCreate(
+        memory_manager: *const JxlMemoryManager,
+        num_worker_threads: usize,
+    ) -> *mut ::std::os::raw::c_void;
+}
+extern "C" {
+    pub fn JxlThreadParallelRunnerDestroy(runner_opaque: *mut ::std::os::raw::c_void);
+}
+extern "C" {
+    pub fn JxlThreadParallelRunnerDefaultNumWorkerThreads() -> usize;
+}
+pub type jpegxl_cms_set_fields_from_icc_func = ::std::option::Option<
+    unsafe extern "C" fn(
+        user_data: *mut ::std::os::raw::c_void,
+        icc_data: *const u8,
+        icc_size: usize,
+        c: *mut JxlColorEncoding,
+        cmyk: *mut ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int,
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlColorProfile {
+    pub icc: JxlColorProfile__bindgen_ty_1,
+    pub color_encoding: JxlColorEncoding,
+    pub num_channels: usize,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlColorProfile__bindgen_ty_1 {
+    pub data: *const u8,
+    pub size: usize,
+}
+impl Default for JxlColorProfile__bindgen_ty_1 {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+impl Default for JxlColorProfile {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+pub type jpegxl_cms_init_func = ::std::option::Option<
+    unsafe extern "C" fn(
+        init_data: *mut ::std::os::raw::c_void,
+        num_threads: usize,
+        pixels_per_thread: usize,
+        input_profile: *const JxlColorProfile,
+        output_profile: *const JxlColorProfile,
+        intensity_target: f32,
+    ) -> *mut ::std::os::raw::c_void,
+>;
+pub type jpegxl_cms_get_buffer_func = ::std::option::Option<
+    unsafe extern "C" fn(user_data: *mut ::std::os::raw::c_void, thread: usize) -> *mut f32,
+>;
+pub type jpegxl_cms_run_func = ::std::option::Option<
+    unsafe extern "C" fn(
+        user_data: *mut ::std::os::raw::c_void,
+        thread: usize,
+        input_buffer: *const f32,
+        output_buffer: *mut f32,
+        num_pixels: usize,
+    ) -> ::std::os::raw::c_int,
+>;
+pub type jpegxl_cms_destroy_func =
+    ::std::option::Option<unsafe extern "C" fn(arg1: *mut ::std::os::raw::c_void)>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlCmsInterface {
+    pub set_fields_data: *mut ::std::os::raw::c_void,
+    pub set_fields_from_icc: jpegxl_cms_set_fields_from_icc_func,
+    pub init_data: *mut ::std::os::raw::c_void,
+    pub init: jpegxl_cms_init_func,
+    pub get_src_buf: jpegxl_cms_get_buffer_func,
+    pub get_dst_buf: jpegxl_cms_get_buffer_func,
+    pub run: jpegxl_cms_run_func,
+    pub destroy: jpegxl_cms_destroy_func,
+}
+impl Default for JxlCmsInterface {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+extern "C" {
+    pub fn JxlEncoderVersion() -> u32;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlEncoderStruct {
+    _unused: [u8; 0],
+}
+pub type JxlEncoder = JxlEncoderStruct;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlEncoderFrameSettingsStruct {
+    _unused: [u8; 0],
+}
+pub type JxlEncoderFrameSettings = JxlEncoderFrameSettingsStruct;
+pub type JxlEncoderOptions = JxlEncoderFrameSettings;
+pub const JXL_ENC_SUCCESS: JxlEncoderStatus = 0;
+pub const JXL_ENC_ERROR: JxlEncoderStatus = 1;
+pub const JXL_ENC_NEED_MORE_OUTPUT: JxlEncoderStatus = 2;
+pub const JXL_ENC_NOT_SUPPORTED: JxlEncoderStatus = 3;
+pub type JxlEncoderStatus = ::std::os::raw::c_uint;
+pub const JXL_ENC_ERR_OK: JxlEncoderError = 0;
+pub const JXL_ENC_ERR_GENERIC: JxlEncoderError = 1;
+pub const JXL_ENC_ERR_OOM: JxlEncoderError = 2;
+pub const JXL_ENC_ERR_JBRD: JxlEncoderError = 3;
+pub const JXL_ENC_ERR_BAD_INPUT: JxlEncoderError = 4;
+pub const JXL_ENC_ERR_NOT_SUPPORTED: JxlEncoderError = 128;
+pub const JXL_ENC_ERR_API_USAGE: JxlEncoderError = 129;
+pub type JxlEncoderError = ::std::os::raw::c_uint;
+pub const JXL_ENC_FRAME_SETTING_EFFORT: JxlEncoderFrameSettingId = 0;
+pub const JXL_ENC_FRAME_SETTING_DECODING_SPEED: JxlEncoderFrameSettingId = 1;
+pub const JXL_ENC_FRAME_SETTING_RESAMPLING: JxlEncoderFrameSettingId = 2;
+pub const JXL_ENC_FRAME_SETTING_EXTRA_CHANNEL_RESAMPLING: JxlEncoderFrameSettingId = 3;
+pub const JXL_ENC_FRAME_SETTING_ALREADY_DOWNSAMPLED: JxlEncoderFrameSettingId = 4;
+pub const JXL_ENC_FRAME_SETTING_PHOTON_NOISE: JxlEncoderFrameSettingId = 5;
+pub const JXL_ENC_FRAME_SETTING_NOISE: JxlEncoderFrameSettingId = 6;
+pub const JXL_ENC_FRAME_SETTING_DOTS: JxlEncoderFrameSettingId = 7;
+pub const JXL_ENC_FRAME_SETTING_PATCHES: JxlEncoderFrameSettingId = 8;
+pub const JXL_ENC_FRAME_SETTING_EPF: JxlEncoderFrameSettingId = 9;
+pub const JXL_ENC_FRAME_SETTING_GABORISH: JxlEncoderFrameSettingId = 10;
+pub const JXL_ENC_FRAME_SETTING_MODULAR: JxlEncoderFrameSettingId = 11;
+pub const JXL_ENC_FRAME_SETTING_KEEP_INVISIBLE: JxlEncoderFrameSettingId = 12;
+pub const JXL_ENC_FRAME_SETTING_GROUP_ORDER: JxlEncoderFrameSettingId = 13;
+pub const JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_X: JxlEncoderFrameSettingId = 14;
+pub const JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_Y: JxlEncoderFrameSettingId = 15;
+pub const JXL_ENC_FRAME_SETTING_RESPONSIVE: JxlEncoderFrameSettingId = 16;
+pub const JXL_ENC_FRAME_SETTING_PROGRESSIVE_AC: JxlEncoderFrameSettingId = 17;
+pub const JXL_ENC_FRAME_SETTING_QPROGRESSIVE_AC: JxlEncoderFrameSettingId = 18;
+pub const JXL_ENC_FRAME_SETTING_PROGRESSIVE_DC: JxlEncoderFrameSettingId = 19;
+pub const JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GLOBAL_PERCENT: JxlEncoderFrameSettingId = 20;
+pub const JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GROUP_PERCENT: JxlEncoderFrameSettingId = 21;
+pub const JXL_ENC_FRAME_SETTING_PALETTE_COLORS: JxlEncoderFrameSettingId = 22;
+pub const JXL_ENC_FRAME_SETTING_LOSSY_PALETTE: JxlEncoderFrameSettingId = 23;
+pub const JXL_ENC_FRAME_SETTING_COLOR_TRANSFORM: JxlEncoderFrameSettingId = 24;
+pub const JXL_ENC_FRAME_SETTING_MODULAR_COLOR_SPACE: JxlEncoderFrameSettingId = 25;
+pub const JXL_ENC_FRAME_SETTING_MODULAR_GROUP_SIZE: JxlEncoderFrameSettingId = 26;
+pub const JXL_ENC_FRAME_SETTING_MODULAR_PREDICTOR: JxlEncoderFrameSettingId = 27;
+pub const JXL_ENC_FRAME_SETTING_MODULAR_MA_TREE_LEARNING_PERCENT: JxlEncoderFrameSettingId = 28;
+pub const JXL_ENC_FRAME_SETTING_MODULAR_NB_PREV_CHANNELS: JxlEncoderFrameSettingId = 29;
+pub const JXL_ENC_FRAME_SETTING_JPEG_RECON_CFL: JxlEncoderFrameSettingId = 30;
+pub const JXL_ENC_FRAME_INDEX_BOX: JxlEncoderFrameSettingId = 31;
+pub const JXL_ENC_FRAME_SETTING_BROTLI_EFFORT: JxlEncoderFrameSettingId = 32;
+pub const JXL_ENC_FRAME_SETTING_FILL_ENUM: JxlEncoderFrameSettingId = 65535;
+pub type JxlEncoderFrameSettingId = ::std::os::raw::c_uint;
+extern "C" {
+    pub fn JxlEncoderCreate(memory_manager: *const JxlMemoryManager) -> *mut JxlEncoder;
+}
+extern "C" {
+    pub fn JxlEncoderReset(enc: *mut JxlEncoder);
+}
+extern "C" {
+    pub fn JxlEncoderDestroy(enc: *mut JxlEncoder);
+}
+extern "C" {
+    pub fn JxlEncoderSetCms(enc: *mut JxlEncoder, cms: JxlCmsInterface);
+}
+extern "C" {
+    pub fn JxlEncoderSetParallelRunner(
+        enc: *mut JxlEncoder,
+        parallel_runner: JxlParallelRunner,
+        parallel_runner_opaque: *mut ::std::os::raw::c_void,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderGetError(enc: *mut JxlEncoder) -> JxlEncoderError;
+}
+extern "C" {
+    pub fn JxlEncoderProcessOutput(
+        enc: *mut JxlEncoder,
+        next_out: *mut *mut u8,
+        avail_out: *mut usize,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetFrameHeader(
+        frame_settings: *mut JxlEncoderFrameSettings,
+        frame_header: *const JxlFrameHeader,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetExtraChannelBlendInfo(
+        frame_settings: *mut JxlEncoderFrameSettings,
+        index: usize,
+        blend_info: *const JxlBlendInfo,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetFrameName(
+        frame_settings: *mut JxlEncoderFrameSettings,
+        frame_name: *const ::std::os::raw::c_char,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderAddJPEGFrame(
+        frame_settings: *const JxlEncoderFrameSettings,
+        buffer: *const u8,
+        size: usize,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderAddImageFrame(
+        frame_settings: *const JxlEncoderFrameSettings,
+        pixel_format: *const JxlPixelFormat,
+        buffer: *const ::std::os::raw::c_void,
+        size: usize,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetExtraChannelBuffer(
+        frame_settings: *const JxlEncoderFrameSettings,
+        pixel_format: *const JxlPixelFormat,
+        buffer: *const ::std::os::raw::c_void,
+        size: usize,
+        index: u32,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderAddBox(
+        enc: *mut JxlEncoder,
+        type_: *const ::std::os::raw::c_char,
+        contents: *const u8,
+        size: usize,
+        compress_box: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderUseBoxes(enc: *mut JxlEncoder) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderCloseBoxes(enc: *mut JxlEncoder);
+}
+extern "C" {
+    pub fn JxlEncoderCloseFrames(enc: *mut JxlEncoder);
+}
+extern "C" {
+    pub fn JxlEncoderCloseInput(enc: *mut JxlEncoder);
+}
+extern "C" {
+    pub fn JxlEncoderSetColorEncoding(
+        enc: *mut JxlEncoder,
+        color: *const JxlColorEncoding,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetICCProfile(
+        enc: *mut JxlEncoder,
+        icc_profile: *const u8,
+        size: usize,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderInitBasicInfo(info: *mut JxlBasicInfo);
+}
+extern "C" {
+    pub fn JxlEncoderInitFrameHeader(frame_header: *mut JxlFrameHeader);
+}
+extern "C" {
+    pub fn JxlEncoderInitBlendInfo(blend_info: *mut JxlBlendInfo);
+}
+extern "C" {
+    pub fn JxlEncoderSetBasicInfo(
+        enc: *mut JxlEncoder,
+        info: *const JxlBasicInfo,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderInitExtraChannelInfo(
+        type_: JxlExtraChannelType,
+        info: *mut JxlExtraChannelInfo,
+    );
+}
+extern "C" {
+    pub fn JxlEncoderSetExtraChannelInfo(
+        enc: *mut JxlEncoder,
+        index: usize,
+        info: *const JxlExtraChannelInfo,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetExtraChannelName(
+        enc: *mut JxlEncoder,
+        index: usize,
+        name: *const ::std::os::raw::c_char,
+        size: usize,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderFrameSettingsSetOption(
+        frame_settings: *mut JxlEncoderFrameSettings,
+        option: JxlEncoderFrameSettingId,
+        value: i64,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderFrameSettingsSetFloatOption(
+        frame_settings: *mut JxlEncoderFrameSettings,
+        option: JxlEncoderFrameSettingId,
+        value: f32,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderUseContainer(
+        enc: *mut JxlEncoder,
+        use_container: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderStoreJPEGMetadata(
+        enc: *mut JxlEncoder,
+        store_jpeg_metadata: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetCodestreamLevel(
+        enc: *mut JxlEncoder,
+        level: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderGetRequiredCodestreamLevel(enc: *const JxlEncoder) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn JxlEncoderSetFrameLossless(
+        frame_settings: *mut JxlEncoderFrameSettings,
+        lossless: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderOptionsSetLossless(
+        arg1: *mut JxlEncoderOptions,
+        arg2: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderOptionsSetEffort(
+        options: *mut JxlEncoderOptions,
+        effort: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderOptionsSetDecodingSpeed(
+        options: *mut JxlEncoderOptions,
+        tier: ::std::os::raw::c_int,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderSetFrameDistance(
+        frame_settings: *mut JxlEncoderFrameSettings,
+        distance: f32,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderOptionsSetDistance(
+        arg1: *mut JxlEncoderOptions,
+        arg2: f32,
+    ) -> JxlEncoderStatus;
+}
+extern "C" {
+    pub fn JxlEncoderFrameSettingsCreate(
+        enc: *mut JxlEncoder,
+        source: *const JxlEncoderFrameSettings,
+    ) -> *mut JxlEncoderFrameSettings;
+}
+extern "C" {
+    pub fn JxlEncoderOptionsCreate(
+        arg1: *mut JxlEncoder,
+        arg2: *const JxlEncoderOptions,
+    ) -> *mut JxlEncoderOptions;
+}
+extern "C" {
+    pub fn JxlColorEncodingSetToSRGB(
+        color_encoding: *mut JxlColorEncoding,
+        is_gray: ::std::os::raw::c_int,
+    );
+}
+extern "C" {
+    pub fn JxlColorEncodingSetToLinearSRGB(
+        color_encoding: *mut JxlColorEncoding,
+        is_gray: ::std::os::raw::c_int,
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlButteraugliApiStruct {
+    _unused: [u8; 0],
+}
+pub type JxlButteraugliApi = JxlButteraugliApiStruct;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JxlButteraugliResultStruct {
+    _unused: [u8; 0],
+}
+pub type JxlButteraugliResult = JxlButteraugliResultStruct;
+extern "C" {
+    pub fn JxlButteraugliResultDestroy(result: *mut JxlButteraugliResult);
+}
+extern "C" {
+    pub fn JxlButteraugliApiCreate(
+        memory_manager: *const JxlMemoryManager,
+    ) -> *mut JxlButteraugliApi;
+}
+extern "C" {
+    pub fn JxlButteraugliApiSetParallelRunner(
+        api: *mut JxlButteraugliApi,
+        parallel_runner: JxlParallelRunner,
+        parallel_runner_opaque: *mut ::std::os::raw::c_void,
+    );
+}
+extern "C" {
+    pub fn JxlButteraugliApiSetHFAsymmetry(api: *mut JxlButteraugliApi, v: f32);
+}
+extern "C" {
+    pub fn JxlButteraugliApiSetIntensityTarget(api: *mut JxlButteraugliApi, v: f32);
+}
+extern "C" {
+    pub fn JxlButteraugliApiDestroy(api: *mut JxlButteraugliApi);
+}
+extern "C" {
+    pub fn JxlButteraugliCompute(
+        api: *const JxlButteraugliApi,
+        xsize: u32,
+        ysize: u32,
+        pixel_format_orig: *const JxlPixelFormat,
+        buffer_orig: *const ::std::os::raw::c_void,
+        size_orig: usize,
+        pixel_format_dist: *const JxlPixelFormat,
+        buffer_dist: *const ::std::os::raw::c_void,
+        size_dist: usize,
+    ) -> *mut JxlButteraugliResult;
+}
+extern "C" {
+    pub fn JxlButteraugliResultGetMaxDistance(result: *const JxlButteraugliResult) -> f32;
+}
+extern "C" {
+    pub fn JxlButteraugliResultGetDistance(result: *const JxlButteraugliResult, pnorm: f32) -> f32;
+}
+extern "C" {
+    pub fn JxlButteraugliResultGetDistmap(
+        result: *const JxlButteraugliResult,
+        buffer: *mut *const f32,
+        row_stride: *mut u32,
+    );
+}
@@ -3,3 +3,47 @@
 #![allow(non_upper_case_globals)]
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+/** A libjxl version, as returned by `JxlDecoderVersion` and `JxlEncoderVersion` */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JxlVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl JxlVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        JxlVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /** Splits a version encoded as `major * 1000000 + minor * 1000 + patch` */
+    pub const fn from_u32(version: u32) -> Self {
+        JxlVersion::new(
+            version / 1_000_000,
+            version / 1_000 % 1_000,
+            version % 1_000,
+        )
+    }
+
+    /** The version of the linked decoder library */
+    pub fn decoder() -> Self {
+        JxlVersion::from_u32(unsafe { JxlDecoderVersion() })
+    }
+
+    /** The version of the linked encoder library */
+    #[cfg(feature = "encoder")]
+    pub fn encoder() -> Self {
+        JxlVersion::from_u32(unsafe { JxlEncoderVersion() })
+    }
+}
+
+impl std::fmt::Display for JxlVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
@@ -0,0 +1,19 @@
+#![cfg(feature = "bindgen")]
+
+#[test]
+fn test_shipped_bindings() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+    let shipped_path = env!("LIBJXL_SYS_SHIPPED_BINDINGS");
+    let shipped = std::fs::read_to_string(shipped_path).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't read the shipped bindings at {}: {}",
+            shipped_path, err
+        )
+    });
+
+    assert!(
+        shipped == generated,
+        "The shipped bindings at {} are outdated, run update-bindings.sh to update them",
+        shipped_path
+    );
+}
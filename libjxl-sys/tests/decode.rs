@@ -7,6 +7,8 @@ fn test_version() {
     unsafe {
         assert_eq!(JxlDecoderVersion(), 7000);
     }
+    assert_eq!(JxlVersion::decoder(), JxlVersion::new(0, 7, 0));
+    assert_eq!(JxlVersion::decoder().to_string(), "0.7.0");
 }
 
 macro_rules! try_dec {
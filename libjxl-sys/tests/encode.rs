@@ -1,3 +1,5 @@
+#![cfg(feature = "encoder")]
+
 use libjxl_sys::*;
 mod decode;
 
@@ -6,6 +8,8 @@ fn test_version() {
     unsafe {
         assert_eq!(JxlEncoderVersion(), 7000);
     }
+    assert_eq!(JxlVersion::encoder(), JxlVersion::new(0, 7, 0));
+    assert_eq!(JxlVersion::encoder().to_string(), "0.7.0");
 }
 
 // Ported version of https://gitlab.com/wg1/jpeg-xl/-/blob/v0.2/examples/encode_oneshot.cc
@@ -0,0 +1,19 @@
+#!/bin/sh
+# Regenerates the shipped bindings in src/bindings/ with bindgen, which requires libclang.
+# The build script only writes to OUT_DIR, so this copies the bindings from there.
+set -e
+cd "$(dirname "$0")"
+
+update() {
+    name=$1
+    shift
+    out_dir=$(cargo build -p libjxl-sys --features bindgen "$@" --message-format=json |
+        grep '"reason":"build-script-executed"' |
+        grep '"package_id":"[^"]*libjxl-sys' |
+        sed 's/.*"out_dir":"\([^"]*\)".*/\1/')
+    cp "$out_dir/bindings.rs" "src/bindings/$name"
+    echo "Updated src/bindings/$name"
+}
+
+update v0_7.rs
+update v0_7_decoder.rs --no-default-features --features vendored
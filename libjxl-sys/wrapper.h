@@ -1,4 +1,6 @@
 #include <jxl/decode.h>
-#include <jxl/encode.h>
 #include <jxl/thread_parallel_runner.h>
+#ifndef LIBJXL_SYS_DECODER_ONLY
+#include <jxl/encode.h>
 #include <jxl/butteraugli.h>
+#endif